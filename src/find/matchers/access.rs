@@ -0,0 +1,114 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Implements -readable, -writable and -executable. Like GNU find, these ask
+//! the kernel (via access(2)) rather than looking at the mode bits, so they
+//! take the current user, ACLs and read-only mounts into account. For
+//! directories "executable" means "searchable".
+
+use std::error::Error;
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AccessType {
+    Readable,
+    Writable,
+    Executable,
+}
+
+pub struct AccessMatcher {
+    #[cfg(unix)]
+    mode: libc::c_int,
+}
+
+impl AccessMatcher {
+    #[cfg(unix)]
+    pub fn new(access_type: AccessType) -> Result<AccessMatcher, Box<dyn Error>> {
+        let mode = match access_type {
+            AccessType::Readable => libc::R_OK,
+            AccessType::Writable => libc::W_OK,
+            AccessType::Executable => libc::X_OK,
+        };
+        Ok(AccessMatcher { mode })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new(_access_type: AccessType) -> Result<AccessMatcher, Box<dyn Error>> {
+        Err(From::from(
+            "Access checking is not available on this platform",
+        ))
+    }
+
+    pub fn new_box(access_type: AccessType) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(AccessMatcher::new(access_type)?))
+    }
+}
+
+impl Matcher for AccessMatcher {
+    #[cfg(unix)]
+    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        // a path can't contain a nul byte, so this can't really fail
+        let path = match CString::new(file_info.path().as_os_str().as_bytes()) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        // Failure here just means "no", as with GNU find, so errors aren't
+        // reported.
+        unsafe { libc::access(path.as_ptr(), self.mode) == 0 }
+    }
+
+    #[cfg(not(unix))]
+    fn matches(&self, _dummy_file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use std::fs::{self, File};
+    use std::os::unix::fs::PermissionsExt;
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn executable_matches_searchable_directories() {
+        let temp_dir = TempDir::new("access_matcher").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        fs::create_dir(temp_dir.path().join("dir")).unwrap();
+        let file_path = temp_dir.path().join("file");
+        File::create(&file_path).unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o644)).unwrap();
+        let script_path = temp_dir.path().join("script");
+        File::create(&script_path).unwrap();
+        fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let dir = get_dir_entry_for(&temp_dir_path, "dir");
+        let file = get_dir_entry_for(&temp_dir_path, "file");
+        let script = get_dir_entry_for(&temp_dir_path, "script");
+        let deps = FakeDependencies::new();
+
+        let matcher = AccessMatcher::new(AccessType::Executable).unwrap();
+        assert!(matcher.matches(&dir, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&script, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&file, &mut deps.new_matcher_io()));
+
+        let matcher = AccessMatcher::new(AccessType::Readable).unwrap();
+        assert!(matcher.matches(&dir, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&file, &mut deps.new_matcher_io()));
+
+        let matcher = AccessMatcher::new(AccessType::Writable).unwrap();
+        assert!(matcher.matches(&file, &mut deps.new_matcher_io()));
+    }
+}
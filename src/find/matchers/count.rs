@@ -0,0 +1,82 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::cell::Cell;
+use std::io::{stderr, ErrorKind, Write};
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+/// A non-standard extension that counts the files it's applied to, and
+/// prints just that number once the search is over, like piping -print into
+/// wc -l (but without getting confused by names with newlines in them).
+pub struct CountMatcher {
+    count: Cell<u64>,
+}
+
+impl CountMatcher {
+    pub fn new() -> CountMatcher {
+        CountMatcher {
+            count: Cell::new(0),
+        }
+    }
+
+    pub fn new_box() -> Box<dyn Matcher> {
+        Box::new(CountMatcher::new())
+    }
+}
+
+impl Matcher for CountMatcher {
+    fn matches(&self, _: &DirEntry, _: &mut MatcherIO) -> bool {
+        self.count.set(self.count.get() + 1);
+        true
+    }
+
+    // like an action, so there's no implicit -print
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
+    fn finished(&self, matcher_io: &mut MatcherIO) {
+        let result = writeln!(
+            matcher_io.deps.get_output().borrow_mut(),
+            "{}",
+            self.count.get()
+        );
+        match result {
+            Ok(_) => {}
+            Err(ref e) if e.kind() == ErrorKind::BrokenPipe => {}
+            Err(e) => {
+                writeln!(&mut stderr(), "find: write error: {}", e).unwrap();
+                matcher_io.mark_error();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::Matcher;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn count_matcher() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+        let matcher = CountMatcher::new();
+
+        assert!(matcher.has_side_effects());
+        for _ in 0..3 {
+            assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        }
+        assert_eq!(deps.get_output_as_string(), "");
+
+        matcher.finished(&mut deps.new_matcher_io());
+        assert_eq!(deps.get_output_as_string(), "3\n");
+    }
+}
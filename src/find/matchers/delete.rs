@@ -7,6 +7,7 @@
  * file that was distributed with this source code.
  */
 
+use std::cell::Cell;
 use std::fs::{self, FileType};
 use std::io::{self, stderr, Write};
 use std::path::Path;
@@ -15,19 +16,93 @@ use walkdir::DirEntry;
 
 use super::{Matcher, MatcherIO};
 
-pub struct DeleteMatcher;
+/// Whether the directory at `path` is on a different device from its parent,
+/// which, as a safety measure, we refuse to delete. This doesn't catch a bind
+/// mount of a directory from the same file system, which looks just like an
+/// ordinary directory here.
+#[cfg(unix)]
+fn is_mount_point(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let parent = fs::metadata(path.join(".."))?;
+    Ok(fs::symlink_metadata(path)?.dev() != parent.dev())
+}
+
+#[cfg(not(unix))]
+fn is_mount_point(_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// What --delete-stats reports once the search is over.
+#[derive(Default)]
+struct DeleteStats {
+    files: Cell<u64>,
+    directories: Cell<u64>,
+    bytes: Cell<u64>,
+    dry_run: Cell<bool>,
+}
+
+fn plural(count: u64, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+impl DeleteStats {
+    /// Directories' own sizes aren't counted towards the bytes freed.
+    fn record(&self, file_type: FileType, size: u64, dry_run: bool) {
+        if file_type.is_dir() {
+            self.directories.set(self.directories.get() + 1);
+        } else {
+            self.files.set(self.files.get() + 1);
+            self.bytes.set(self.bytes.get() + size);
+        }
+        self.dry_run.set(dry_run);
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} {} and {} ({})",
+            if self.dry_run.get() {
+                "would have deleted"
+            } else {
+                "deleted"
+            },
+            plural(self.files.get(), "file", "files"),
+            plural(self.directories.get(), "directory", "directories"),
+            plural(self.bytes.get(), "byte", "bytes"),
+        )
+    }
+}
+
+pub struct DeleteMatcher {
+    stats: Option<DeleteStats>,
+}
 
 impl DeleteMatcher {
     pub fn new() -> DeleteMatcher {
-        DeleteMatcher
+        DeleteMatcher { stats: None }
     }
 
-    pub fn new_box() -> io::Result<Box<dyn Matcher>> {
-        Ok(Box::new(DeleteMatcher::new()))
+    /// For --delete-stats, which counts what gets deleted (or, with
+    /// --dry-run, what would have been) and reports it on stderr at the end.
+    pub fn new_with_stats() -> DeleteMatcher {
+        DeleteMatcher {
+            stats: Some(DeleteStats::default()),
+        }
+    }
+
+    pub fn new_box(stats: bool) -> io::Result<Box<dyn Matcher>> {
+        Ok(Box::new(if stats {
+            DeleteMatcher::new_with_stats()
+        } else {
+            DeleteMatcher::new()
+        }))
     }
 
     fn delete(&self, file_path: &Path, file_type: FileType) -> io::Result<()> {
         if file_type.is_dir() {
+            if is_mount_point(file_path)? {
+                return Err(io::Error::other("refusing to delete a mount point"));
+            }
             fs::remove_dir(file_path)
         } else {
             fs::remove_file(file_path)
@@ -36,7 +111,7 @@ impl DeleteMatcher {
 }
 
 impl Matcher for DeleteMatcher {
-    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
         let path = file_info.path();
         let path_str = path.to_string_lossy();
 
@@ -47,10 +122,28 @@ impl Matcher for DeleteMatcher {
             return true;
         }
 
-        match self.delete(path, file_info.file_type()) {
-            Ok(_) => true,
+        // The file type walkdir gives us may have come from readdir's d_type,
+        // which can be out of date by now (or, on some file systems, just
+        // wrong), so choosing between rmdir and unlink needs a fresh lstat.
+        let metadata = fs::symlink_metadata(path);
+        let result = metadata.and_then(|metadata| {
+            if matcher_io.dry_run() {
+                writeln!(&mut stderr(), "would delete {}", path_str).unwrap();
+            } else {
+                self.delete(path, metadata.file_type())?;
+            }
+            Ok(metadata)
+        });
+        match result {
+            Ok(metadata) => {
+                if let Some(ref stats) = self.stats {
+                    stats.record(metadata.file_type(), metadata.len(), matcher_io.dry_run());
+                }
+                true
+            }
             Err(e) => {
                 writeln!(&mut stderr(), "Failed to delete {}: {}", path_str, e).unwrap();
+                matcher_io.mark_error();
                 false
             }
         }
@@ -59,6 +152,12 @@ impl Matcher for DeleteMatcher {
     fn has_side_effects(&self) -> bool {
         true
     }
+
+    fn finished(&self, _matcher_io: &mut MatcherIO) {
+        if let Some(ref stats) = self.stats {
+            writeln!(&mut stderr(), "{}", stats.summary()).unwrap();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -69,6 +168,7 @@ mod tests {
     use super::*;
     use crate::find::matchers::tests::get_dir_entry_for;
     use crate::find::tests::FakeDependencies;
+    use crate::find::Config;
 
     #[test]
     fn delete_matcher() {
@@ -99,4 +199,59 @@ mod tests {
             "DeleteMatcher should actually delete (empty) directories it matches",
         );
     }
+
+    #[test]
+    fn delete_matcher_uses_current_file_type() {
+        let matcher = DeleteMatcher::new();
+        let deps = FakeDependencies::new();
+
+        // walkdir saw a file, but by the time we get to it, it's a directory
+        let temp_dir = TempDir::new("delete_matcher_uses_current_file_type").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        File::create(temp_dir.path().join("victim")).expect("created test file");
+        let entry = get_dir_entry_for(&temp_dir_path, "victim");
+        assert!(entry.file_type().is_file());
+        fs::remove_file(temp_dir.path().join("victim")).unwrap();
+        fs::create_dir(temp_dir.path().join("victim")).unwrap();
+
+        let mut matcher_io = deps.new_matcher_io();
+        assert!(matcher.matches(&entry, &mut matcher_io));
+        assert!(!matcher_io.had_error());
+        assert!(!temp_dir.path().join("victim").exists());
+    }
+
+    #[test]
+    fn delete_matcher_stats() {
+        let matcher = DeleteMatcher::new_with_stats();
+        let deps = FakeDependencies::new();
+
+        let temp_dir = TempDir::new_in("test_data", "delete_matcher_stats").expect("made temp dir");
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        fs::create_dir(temp_dir.path().join("subdir")).expect("created test dir");
+        fs::write(temp_dir.path().join("subdir/three"), "abc").expect("created test file");
+        fs::write(temp_dir.path().join("four"), "abcd").expect("created test file");
+        for name in &["three", "subdir", "four"] {
+            let entry = get_dir_entry_for(&temp_dir_path, name);
+            assert!(matcher.matches(&entry, &mut deps.new_matcher_io()));
+        }
+        assert_eq!(
+            matcher.stats.as_ref().unwrap().summary(),
+            "deleted 2 files and 1 directory (7 bytes)"
+        );
+
+        let matcher = DeleteMatcher::new_with_stats();
+        fs::write(temp_dir.path().join("one"), "a").expect("created test file");
+        let entry = get_dir_entry_for(&temp_dir_path, "one");
+        let config = Config {
+            dry_run: true,
+            ..Config::default()
+        };
+        let mut matcher_io = MatcherIO::new(&deps, &config);
+        assert!(matcher.matches(&entry, &mut matcher_io));
+        assert!(temp_dir.path().join("one").exists());
+        assert_eq!(
+            matcher.stats.as_ref().unwrap().summary(),
+            "would have deleted 1 file and 0 directories (1 byte)"
+        );
+    }
 }
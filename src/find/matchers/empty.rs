@@ -0,0 +1,91 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::fs;
+use std::io::{stderr, Write};
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+/// This matcher checks whether a file is an empty regular file or an empty
+/// directory. The file type comes from the directory entry itself, so we
+/// only ever stat regular files and only ever read directories.
+pub struct EmptyMatcher;
+
+impl EmptyMatcher {
+    pub fn new() -> EmptyMatcher {
+        EmptyMatcher
+    }
+
+    pub fn new_box() -> Box<dyn Matcher> {
+        Box::new(EmptyMatcher::new())
+    }
+}
+
+impl Matcher for EmptyMatcher {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        let file_type = file_info.file_type();
+        let result = if file_type.is_file() {
+            matcher_io
+                .metadata(file_info)
+                .map(|metadata| metadata.len() == 0)
+                .map_err(From::from)
+        } else if file_type.is_dir() {
+            fs::read_dir(file_info.path()).map(|mut it| it.next().is_none())
+        } else {
+            return false;
+        };
+        match result {
+            Ok(is_empty) => is_empty,
+            Err(e) => {
+                writeln!(
+                    &mut stderr(),
+                    "Error checking whether {} is empty: {}",
+                    file_info.path().to_string_lossy(),
+                    e
+                )
+                .unwrap();
+                matcher_io.mark_error();
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::Matcher;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn empty_matcher() {
+        let temp_dir = TempDir::new("empty_matcher").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        File::create(temp_dir.path().join("empty_file")).expect("create temp file");
+        File::create(temp_dir.path().join("full_file"))
+            .and_then(|mut f| f.write_all(b"x"))
+            .expect("create temp file");
+        fs::create_dir(temp_dir.path().join("empty_dir")).expect("create temp dir");
+
+        let empty_file = get_dir_entry_for(&temp_dir_path, "empty_file");
+        let full_file = get_dir_entry_for(&temp_dir_path, "full_file");
+        let empty_dir = get_dir_entry_for(&temp_dir_path, "empty_dir");
+        let full_dir = get_dir_entry_for("test_data", "simple");
+
+        let matcher = EmptyMatcher::new();
+        let deps = FakeDependencies::new();
+        assert!(matcher.matches(&empty_file, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&full_file, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&empty_dir, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&full_dir, &mut deps.new_matcher_io()));
+    }
+}
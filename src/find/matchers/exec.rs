@@ -4,24 +4,192 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::env;
 use std::error::Error;
 use std::ffi::OsString;
-use std::io::{stderr, Write};
+use std::io::{self, stderr, ErrorKind, Write};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Duration;
+use wait_timeout::ChildExt;
 use walkdir::DirEntry;
 
-use super::{Matcher, MatcherIO};
+use super::{Matcher, MatcherIO, QuotingStyle};
 
 enum Arg {
-    Filename,
+    /// An argument containing one or more {}s, stored as the pieces between
+    /// them ("{}" on its own is two empty pieces).
+    FileArg(Vec<OsString>),
     LiteralArg(OsString),
 }
 
+/// Like GNU find, {} is replaced even when it's only part of an argument (e.g.
+/// "{}.bak"). As a non-standard extension, {{}} is a literal {}.
+fn parse_arg(arg: &str) -> Arg {
+    let mut pieces = vec![OsString::new()];
+    for (i, unescaped) in arg.split("{{}}").enumerate() {
+        if i > 0 {
+            pieces.last_mut().unwrap().push("{}");
+        }
+        for (j, piece) in unescaped.split("{}").enumerate() {
+            if j > 0 {
+                pieces.push(OsString::new());
+            }
+            pieces.last_mut().unwrap().push(piece);
+        }
+    }
+    if pieces.len() == 1 {
+        Arg::LiteralArg(pieces.pop().unwrap())
+    } else {
+        Arg::FileArg(pieces)
+    }
+}
+
+/// Runs `command` to completion or, if there's a timeout, until it runs out.
+/// Returns None if the command had to be killed.
+fn run_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<ExitStatus>> {
+    let mut child = command.spawn()?;
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return child.wait().map(Some),
+    };
+    if let Some(status) = child.wait_timeout(timeout)? {
+        return Ok(Some(status));
+    }
+    // the child may have exited just after the timeout, in which case
+    // there's nothing left to kill, but we still have to reap it
+    let _ = child.kill();
+    child.wait()?;
+    Ok(None)
+}
+
+fn report_timeout(executable: &str, matcher_io: &mut MatcherIO) {
+    writeln!(
+        &mut stderr(),
+        "find: '{}' timed out after {}s and was killed",
+        executable,
+        matcher_io.exec_timeout().unwrap_or_default().as_secs()
+    )
+    .unwrap();
+    matcher_io.mark_error();
+}
+
+/// Whether a command that ran to completion succeeded. One that was killed by
+/// a signal is an error (as in GNU find), not just a false result.
+fn check_status(status: ExitStatus, executable: &str, matcher_io: &mut MatcherIO) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            writeln!(
+                &mut stderr(),
+                "find: '{}' terminated by signal {}",
+                executable,
+                signal
+            )
+            .unwrap();
+            matcher_io.mark_error();
+            return false;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (executable, &matcher_io);
+    status.success()
+}
+
+/// For --dry-run. Each argument is quoted for the shell if it needs it, and
+/// for -execdir, the directory the command would be run in comes after it.
+fn report_dry_run(command: &Command) {
+    let mut line = QuotingStyle::Shell.quote(&command.get_program().to_string_lossy());
+    for arg in command.get_args() {
+        line.push(' ');
+        line.push_str(&QuotingStyle::Shell.quote(&arg.to_string_lossy()));
+    }
+    if let Some(dir) = command.get_current_dir() {
+        line.push_str(" in ");
+        line.push_str(&QuotingStyle::Shell.quote(&dir.to_string_lossy()));
+    }
+    writeln!(&mut stderr(), "would run {}", line).unwrap();
+}
+
+/// The system's limit on the combined size of a new process's arguments and
+/// environment, if there is one.
+#[cfg(unix)]
+pub fn system_arg_max() -> Option<usize> {
+    // safe: sysconf has no preconditions
+    match unsafe { libc::sysconf(libc::_SC_ARG_MAX) } {
+        n if n > 0 => Some(n as usize),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn system_arg_max() -> Option<usize> {
+    None
+}
+
+/// Roughly what exec counts against ARG_MAX for a string: its bytes, its
+/// terminating nul and a pointer to it.
+fn exec_size(len: usize) -> usize {
+    len + 1 + std::mem::size_of::<usize>()
+}
+
+/// What our environment, which every command inherits, counts against
+/// ARG_MAX. It doesn't change while we run, so matchers work it out once.
+fn environment_size() -> usize {
+    // "NAME=value"
+    env::vars_os()
+        .map(|(name, value)| exec_size(name.len() + value.len() + 1))
+        .sum()
+}
+
+/// Roughly what exec counts against ARG_MAX: each argument, plus the
+/// environment.
+fn command_size(command: &Command, environment_size: usize) -> usize {
+    std::iter::once(command.get_program().len())
+        .chain(command.get_args().map(|arg| arg.len()))
+        .map(exec_size)
+        .sum::<usize>()
+        + environment_size
+}
+
+/// Checks that `command` isn't too big to run, so that we can give a clear
+/// error instead of whatever the OS makes of it (E2BIG on unix).
+fn check_arg_max(
+    command: &Command,
+    environment_size: usize,
+    executable: &str,
+    matcher_io: &mut MatcherIO,
+) -> bool {
+    let arg_max = match matcher_io.arg_max() {
+        Some(arg_max) => arg_max,
+        None => return true,
+    };
+    let size = command_size(command, environment_size);
+    if size <= arg_max {
+        return true;
+    }
+    writeln!(
+        &mut stderr(),
+        "find: the command line for '{}' would be too long ({} bytes, \
+         including the environment, but the limit is {})",
+        executable,
+        size,
+        arg_max
+    )
+    .unwrap();
+    matcher_io.mark_error();
+    false
+}
+
 pub struct SingleExecMatcher {
     executable: String,
     args: Vec<Arg>,
     exec_in_parent_dir: bool,
+    environment_size: usize,
 }
 
 impl SingleExecMatcher {
@@ -30,18 +198,13 @@ impl SingleExecMatcher {
         args: &[&str],
         exec_in_parent_dir: bool,
     ) -> Result<SingleExecMatcher, Box<dyn Error>> {
-        let transformed_args = args
-            .iter()
-            .map(|&a| match a {
-                "{}" => Arg::Filename,
-                _ => Arg::LiteralArg(OsString::from(a)),
-            })
-            .collect();
+        let transformed_args = args.iter().map(|&a| parse_arg(a)).collect();
 
         Ok(SingleExecMatcher {
             executable: executable.to_string(),
             args: transformed_args,
             exec_in_parent_dir,
+            environment_size: environment_size(),
         })
     }
 
@@ -59,8 +222,10 @@ impl SingleExecMatcher {
 }
 
 impl Matcher for SingleExecMatcher {
-    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
         let mut command = Command::new(&self.executable);
+        // so that the command can't eat the input of whatever's running us
+        command.stdin(Stdio::null());
         let path_to_file = if self.exec_in_parent_dir {
             if let Some(f) = file_info.path().file_name() {
                 Path::new(".").join(f)
@@ -72,11 +237,22 @@ impl Matcher for SingleExecMatcher {
         };
 
         for arg in &self.args {
-            command.arg(match *arg {
-                Arg::LiteralArg(ref a) => a.as_os_str(),
-                Arg::Filename => path_to_file.as_os_str(),
-            });
+            match *arg {
+                Arg::LiteralArg(ref a) => command.arg(a.as_os_str()),
+                Arg::FileArg(ref parts) => {
+                    let mut arg = OsString::new();
+                    for (i, part) in parts.iter().enumerate() {
+                        if i > 0 {
+                            arg.push(path_to_file.as_os_str());
+                        }
+                        arg.push(part);
+                    }
+                    command.arg(arg)
+                }
+            };
         }
+        // this only changes the child's working directory, never ours, so
+        // there's nothing to restore if the spawn fails
         if self.exec_in_parent_dir {
             if file_info.path() == Path::new(".") {
                 command.current_dir(file_info.path());
@@ -84,10 +260,106 @@ impl Matcher for SingleExecMatcher {
                 command.current_dir(parent);
             }
         }
-        match command.status() {
-            Ok(status) => status.success(),
+        if !check_arg_max(
+            &command,
+            self.environment_size,
+            &self.executable,
+            matcher_io,
+        ) {
+            return false;
+        }
+        if matcher_io.dry_run() {
+            report_dry_run(&command);
+            return true;
+        }
+        match run_with_timeout(&mut command, matcher_io.exec_timeout()) {
+            Ok(Some(status)) => check_status(status, &self.executable, matcher_io),
+            Ok(None) => {
+                report_timeout(&self.executable, matcher_io);
+                false
+            }
+            // distinguish a command that doesn't exist from one that ran
+            // and failed (which isn't an error at all, just a false result)
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                writeln!(
+                    &mut stderr(),
+                    "find: '{}': No such file or directory",
+                    self.executable
+                )
+                .unwrap();
+                matcher_io.mark_error();
+                false
+            }
             Err(e) => {
                 writeln!(&mut stderr(), "Failed to run {}: {}", self.executable, e).unwrap();
+                matcher_io.mark_error();
+                false
+            }
+        }
+    }
+
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+}
+
+/// A non-standard extension that runs a command string through the system
+/// shell, as `sh -c`. The filename is passed to the shell as `$1` and each
+/// `{}` in the command string is replaced with a quoted reference to it, so
+/// the filename itself is never subject to any expansion by the shell. The
+/// replacement is purely textual, so a `{}` inside single quotes (where the
+/// shell doesn't expand `$1`) becomes a literal `"$1"` rather than the
+/// filename.
+pub struct ShellExecMatcher {
+    script: String,
+    environment_size: usize,
+}
+
+impl ShellExecMatcher {
+    #[cfg(unix)]
+    pub fn new(command: &str) -> Result<ShellExecMatcher, Box<dyn Error>> {
+        Ok(ShellExecMatcher {
+            script: command.replace("{}", "\"$1\""),
+            environment_size: environment_size(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new(_command: &str) -> Result<ShellExecMatcher, Box<dyn Error>> {
+        Err(From::from("-execsh is not available on this platform"))
+    }
+
+    pub fn new_box(command: &str) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(ShellExecMatcher::new(command)?))
+    }
+}
+
+impl Matcher for ShellExecMatcher {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        // the extra "sh" is $0, which the shell uses in its error messages
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&self.script)
+            .arg("sh")
+            .arg(file_info.path())
+            .stdin(Stdio::null());
+        if !check_arg_max(&command, self.environment_size, "sh", matcher_io) {
+            return false;
+        }
+        if matcher_io.dry_run() {
+            report_dry_run(&command);
+            return true;
+        }
+        match run_with_timeout(&mut command, matcher_io.exec_timeout()) {
+            Ok(Some(status)) => check_status(status, "sh", matcher_io),
+            Ok(None) => {
+                report_timeout("sh", matcher_io);
+                false
+            }
+            Err(e) => {
+                writeln!(&mut stderr(), "Failed to run sh: {}", e).unwrap();
+                matcher_io.mark_error();
                 false
             }
         }
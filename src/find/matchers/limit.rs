@@ -0,0 +1,80 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::cell::Cell;
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+/// A non-standard extension that matches the first N files it's evaluated
+/// for, and then stops the search (like -quit) after the Nth.
+pub struct LimitMatcher {
+    limit: usize,
+    count: Cell<usize>,
+}
+
+impl LimitMatcher {
+    pub fn new(limit: usize) -> LimitMatcher {
+        LimitMatcher {
+            limit,
+            count: Cell::new(0),
+        }
+    }
+
+    pub fn new_box(limit: usize) -> Box<dyn Matcher> {
+        Box::new(LimitMatcher::new(limit))
+    }
+}
+
+impl Matcher for LimitMatcher {
+    fn matches(&self, _: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        let count = self.count.get() + 1;
+        if count > self.limit {
+            // only possible with a limit of 0
+            matcher_io.mark_quit();
+            return false;
+        }
+        self.count.set(count);
+        if count == self.limit {
+            matcher_io.mark_quit();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::Matcher;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn limit_matcher() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+        let matcher = LimitMatcher::new(2);
+
+        let mut matcher_io = deps.new_matcher_io();
+        assert!(matcher.matches(&abbbc, &mut matcher_io));
+        assert!(!matcher_io.should_quit());
+
+        let mut matcher_io = deps.new_matcher_io();
+        assert!(matcher.matches(&abbbc, &mut matcher_io));
+        assert!(matcher_io.should_quit());
+    }
+
+    #[test]
+    fn zero_limit_matcher() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+        let matcher = LimitMatcher::new(0);
+
+        let mut matcher_io = deps.new_matcher_io();
+        assert!(!matcher.matches(&abbbc, &mut matcher_io));
+        assert!(matcher_io.should_quit());
+    }
+}
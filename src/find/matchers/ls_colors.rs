@@ -0,0 +1,154 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Support for colorizing printed paths by file type, using the same
+//! `LS_COLORS` environment variable (and format) as `ls --color`.
+
+use std::collections::HashMap;
+use std::env;
+use walkdir::DirEntry;
+
+use super::MatcherIO;
+
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+/// The colors GNU ls uses for the common file types when `LS_COLORS` isn't
+/// set.
+const DEFAULT_LS_COLORS: &str =
+    "di=01;34:ln=01;36:pi=40;33:so=01;35:bd=40;33;01:cd=40;33;01:ex=01;32";
+
+/// A parsed `LS_COLORS` specification: a set of SGR parameter strings keyed
+/// either by a two-letter file type code (e.g. "di" for directories) or by
+/// a filename suffix (e.g. "*.tar").
+pub struct LsColors {
+    by_type: HashMap<String, String>,
+    by_suffix: Vec<(String, String)>,
+}
+
+impl LsColors {
+    pub fn new(spec: &str) -> LsColors {
+        let mut by_type = HashMap::new();
+        let mut by_suffix = vec![];
+        for entry in spec.split(':') {
+            let mut parts = entry.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => (key, value),
+                _ => continue,
+            };
+            if let Some(suffix) = key.strip_prefix('*') {
+                by_suffix.push((suffix.to_string(), value.to_string()));
+            } else {
+                by_type.insert(key.to_string(), value.to_string());
+            }
+        }
+        LsColors { by_type, by_suffix }
+    }
+
+    /// Builds an `LsColors` from the `LS_COLORS` environment variable,
+    /// falling back to the same defaults as GNU ls.
+    pub fn from_env() -> LsColors {
+        match env::var("LS_COLORS") {
+            Ok(spec) if !spec.is_empty() => LsColors::new(&spec),
+            _ => LsColors::new(DEFAULT_LS_COLORS),
+        }
+    }
+
+    fn type_code(file_info: &DirEntry, matcher_io: &MatcherIO) -> &'static str {
+        let file_type = file_info.file_type();
+        if file_type.is_dir() {
+            return "di";
+        }
+        if file_type.is_symlink() {
+            return "ln";
+        }
+        #[cfg(unix)]
+        {
+            if file_type.is_fifo() {
+                return "pi";
+            }
+            if file_type.is_socket() {
+                return "so";
+            }
+            if file_type.is_block_device() {
+                return "bd";
+            }
+            if file_type.is_char_device() {
+                return "cd";
+            }
+            if let Ok(metadata) = matcher_io.metadata(file_info) {
+                if metadata.permissions().mode() & 0o111 != 0 {
+                    return "ex";
+                }
+            }
+        }
+        "fi"
+    }
+
+    /// Returns the SGR parameters to use for the given file, if any.
+    pub fn color_for(&self, file_info: &DirEntry, matcher_io: &MatcherIO) -> Option<&str> {
+        let code = LsColors::type_code(file_info, matcher_io);
+        // as with ls, suffix matches only apply to regular files
+        if code == "fi" || code == "ex" {
+            let name = file_info.file_name().to_string_lossy();
+            for (suffix, color) in &self.by_suffix {
+                if name.ends_with(suffix.as_str()) {
+                    return Some(color);
+                }
+            }
+        }
+        self.by_type
+            .get(code)
+            .map(|s| s.as_str())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Wraps `text` in the SGR escape codes appropriate for `file_info`.
+    pub fn paint(&self, file_info: &DirEntry, text: &str, matcher_io: &MatcherIO) -> String {
+        match self.color_for(file_info, matcher_io) {
+            Some(color) => format!("\x1b[{}m{}\x1b[0m", color, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn parses_types_and_suffixes() {
+        let colors = LsColors::new("di=01;34:fi=0:*bbc=01;31:bogus:=1");
+        let dir = get_dir_entry_for("test_data", "simple");
+        let file = get_dir_entry_for("test_data/simple", "abbbc");
+        let other_file = get_dir_entry_for("test_data/size", "512bytes");
+
+        let deps = FakeDependencies::new();
+        let matcher_io = deps.new_matcher_io();
+
+        assert_eq!(colors.color_for(&dir, &matcher_io), Some("01;34"));
+        assert_eq!(colors.color_for(&file, &matcher_io), Some("01;31"));
+        assert_eq!(colors.color_for(&other_file, &matcher_io), Some("0"));
+    }
+
+    #[test]
+    fn paints() {
+        let colors = LsColors::new("di=01;34");
+        let dir = get_dir_entry_for("test_data", "simple");
+        let file = get_dir_entry_for("test_data/simple", "abbbc");
+
+        let deps = FakeDependencies::new();
+        let matcher_io = deps.new_matcher_io();
+
+        assert_eq!(
+            colors.paint(&dir, "simple", &matcher_io),
+            "\x1b[01;34msimple\x1b[0m"
+        );
+        assert_eq!(colors.paint(&file, "abbbc", &matcher_io), "abbbc");
+    }
+}
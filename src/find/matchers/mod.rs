@@ -4,37 +4,64 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+mod access;
+mod count;
 mod delete;
+mod empty;
 pub mod exec;
+mod limit;
 mod logical_matchers;
+mod ls_colors;
 mod name;
+mod newest;
 mod perm;
 mod printer;
 mod prune;
+mod quoting;
+mod rates;
+mod regex_matcher;
+mod samefile;
 mod size;
 mod time;
 mod type_matcher;
 
-use regex::Regex;
 use std::error::Error;
+use std::fmt::Display;
+use std::fs::Metadata;
+use std::io::{stderr, Write};
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 use walkdir::DirEntry;
 
 use super::{Config, Dependencies};
 
+pub use self::ls_colors::LsColors;
+pub use self::quoting::QuotingStyle;
+pub use self::regex_matcher::{RegexAnchor, RegexType};
+
 /// Struct holding references to outputs and any inputs that can't be derived
-/// from the file/directory info.
-pub struct MatcherIO<'a> {
+/// from the file/directory info. Global options (how to print names, -D stat
+/// and so on) are read from the Config.
+pub struct MatcherIO<'a, 'c> {
     should_skip_dir: bool,
+    should_quit: bool,
+    had_error: bool,
+    arg_max: Option<usize>,
+    config: &'c Config,
     deps: &'a dyn Dependencies<'a>,
 }
 
-impl<'a> MatcherIO<'a> {
-    pub fn new(deps: &'a dyn Dependencies<'a>) -> MatcherIO<'a> {
+impl<'a, 'c> MatcherIO<'a, 'c> {
+    pub fn new(deps: &'a dyn Dependencies<'a>, config: &'c Config) -> MatcherIO<'a, 'c> {
         MatcherIO {
             deps,
+            config,
             should_skip_dir: false,
+            should_quit: false,
+            had_error: false,
+            arg_max: None,
         }
     }
 
@@ -46,8 +73,78 @@ impl<'a> MatcherIO<'a> {
         self.should_skip_dir
     }
 
+    /// Asks for the whole search to stop once the current file is done.
+    pub fn mark_quit(&mut self) {
+        self.should_quit = true;
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
+    }
+
+    /// Records that something went wrong while processing the current file
+    /// (e.g. a failed stat or exec). The matcher is still responsible for
+    /// reporting the actual error.
+    pub fn mark_error(&mut self) {
+        self.had_error = true;
+    }
+
+    pub fn had_error(&self) -> bool {
+        self.had_error
+    }
+
+    /// The colors to use when printing file names, if any.
+    pub fn ls_colors(&self) -> Option<&LsColors> {
+        self.config.ls_colors.as_deref()
+    }
+
+    /// How printed file names should be quoted.
+    pub fn quoting_style(&self) -> QuotingStyle {
+        self.config.quoting_style
+    }
+
+    /// How long -exec and -execsh commands may run before being killed.
+    pub fn exec_timeout(&self) -> Option<Duration> {
+        self.config.exec_timeout
+    }
+
+    /// Whether --dry-run was given, where actions that change things
+    /// (-delete and the -exec family) only say what they would have done.
+    pub fn dry_run(&self) -> bool {
+        self.config.dry_run
+    }
+
+    /// Overrides the system's limit on the size of a command line (and
+    /// environment) that -exec will try to run, e.g. for testing.
+    pub fn set_arg_max(&mut self, arg_max: usize) {
+        self.arg_max = Some(arg_max);
+    }
+
+    /// The limit on the size of a command line that -exec will try to run,
+    /// or None if there's no known limit.
+    pub fn arg_max(&self) -> Option<usize> {
+        self.arg_max.or_else(exec::system_arg_max)
+    }
+
+    /// Matchers should get a file's metadata through here rather than
+    /// straight from the DirEntry, so that -D stat can log it. As we never
+    /// follow symlinks, this is always an lstat.
+    pub fn metadata(&self, file_info: &DirEntry) -> walkdir::Result<Metadata> {
+        if self.config.debug_stat {
+            writeln!(
+                &mut stderr(),
+                "debug_stat (lstat) {}",
+                file_info.path().to_string_lossy()
+            )
+            .unwrap();
+        }
+        file_info.metadata()
+    }
+
+    /// The time find started, which is what every time-based test compares
+    /// against. The hidden --now overrides it.
     pub fn now(&self) -> SystemTime {
-        self.deps.now()
+        self.config.now.unwrap_or_else(|| self.deps.now())
     }
 }
 
@@ -75,7 +172,7 @@ pub trait Matcher {
     /// Notification that find has finished processing all directories -
     /// allowing for any cleanup that isn't suitable for destructors (e.g.
     /// blocking calls, I/O etc.)
-    fn finished(&self) {}
+    fn finished(&self, _matcher_io: &mut MatcherIO) {}
 }
 
 pub enum ComparableValue {
@@ -126,63 +223,120 @@ fn are_more_expressions(args: &[&str], index: usize) -> bool {
     (index < args.len() - 1) && args[index + 1] != ")"
 }
 
+/// Helper function for `build_matcher_tree`. A binary operator straight after
+/// a negation (e.g. "! -o") leaves the negation with nothing to apply to;
+/// without this check it would silently apply to the right hand operand.
+fn check_not_after_negation(operator: &str, negation_pending: bool) -> Result<(), Box<dyn Error>> {
+    if negation_pending {
+        return Err(From::from(format!(
+            "invalid expression; you have used a binary operator '{}' \
+             with nothing before it.",
+            operator
+        )));
+    }
+    Ok(())
+}
+
+/// Parses a string of decimal digits, which can only fail if it's too big for
+/// the type it's parsed into. That gets its own error, rather than the
+/// generic "expected a number" one, as the argument was a number.
+fn parse_digits<T: FromStr + Display>(
+    option_name: &str,
+    digits: &str,
+    value_as_string: &str,
+    max: T,
+) -> Result<T, Box<dyn Error>> {
+    digits.parse::<T>().map_err(|_| {
+        From::from(format!(
+            "argument `{}' to {} is too large: the maximum is {}",
+            value_as_string, option_name, max
+        ))
+    })
+}
+
 fn convert_arg_to_number(
     option_name: &str,
     value_as_string: &str,
 ) -> Result<usize, Box<dyn Error>> {
-    match value_as_string.parse::<usize>() {
-        Ok(val) => Ok(val),
-        _ => Err(From::from(format!(
+    // as with str::parse, a leading + is allowed
+    let digits = value_as_string.strip_prefix('+').unwrap_or(value_as_string);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(From::from(format!(
             "Expected a positive decimal integer argument to {}, but got \
              `{}'",
             option_name, value_as_string
-        ))),
+        )));
+    }
+    parse_digits(option_name, digits, value_as_string, usize::MAX)
+}
+
+/// Splits a [+-]N argument (optionally followed by a suffix, e.g. the unit
+/// for -size) into the sign, the digits and the suffix.
+fn split_comparable_value(value_as_string: &str) -> Option<(&str, &str, &str)> {
+    let digits_start = if value_as_string.starts_with(['+', '-']) {
+        1
+    } else {
+        0
+    };
+    let digits_end = value_as_string[digits_start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(value_as_string.len(), |end| digits_start + end);
+    if digits_end == digits_start {
+        return None;
     }
+    Some((
+        &value_as_string[..digits_start],
+        &value_as_string[digits_start..digits_end],
+        &value_as_string[digits_end..],
+    ))
+}
+
+/// Every [+-]N argument goes through here, so they all get the same overflow
+/// checking.
+fn to_comparable_value(
+    option_name: &str,
+    (sign, digits): (&str, &str),
+    value_as_string: &str,
+) -> Result<ComparableValue, Box<dyn Error>> {
+    let val = parse_digits(option_name, digits, value_as_string, u64::MAX)?;
+    Ok(match sign {
+        "+" => ComparableValue::MoreThan(val),
+        "-" => ComparableValue::LessThan(val),
+        _ => ComparableValue::EqualTo(val),
+    })
 }
 
 fn convert_arg_to_comparable_value(
     option_name: &str,
     value_as_string: &str,
 ) -> Result<ComparableValue, Box<dyn Error>> {
-    let re = Regex::new(r"([+-]?)(\d+)$")?;
-    if let Some(groups) = re.captures(value_as_string) {
-        if let Ok(val) = groups[2].parse::<u64>() {
-            return Ok(match &groups[1] {
-                "+" => ComparableValue::MoreThan(val),
-                "-" => ComparableValue::LessThan(val),
-                _ => ComparableValue::EqualTo(val),
-            });
-        }
-    }
-    Err(From::from(format!(
-        "Expected a decimal integer (with optional + or - prefix) argument \
-         to {}, but got `{}'",
-        option_name, value_as_string
-    )))
+    match split_comparable_value(value_as_string) {
+        Some((sign, digits, "")) => {
+            to_comparable_value(option_name, (sign, digits), value_as_string)
+        }
+        _ => Err(From::from(format!(
+            "Expected a decimal integer (with optional + or - prefix) argument \
+             to {}, but got `{}'",
+            option_name, value_as_string
+        ))),
+    }
 }
 
 fn convert_arg_to_comparable_value_and_suffix(
     option_name: &str,
     value_as_string: &str,
 ) -> Result<(ComparableValue, String), Box<dyn Error>> {
-    let re = Regex::new(r"([+-]?)(\d+)(.*)$")?;
-    if let Some(groups) = re.captures(value_as_string) {
-        if let Ok(val) = groups[2].parse::<u64>() {
-            return Ok((
-                match &groups[1] {
-                    "+" => ComparableValue::MoreThan(val),
-                    "-" => ComparableValue::LessThan(val),
-                    _ => ComparableValue::EqualTo(val),
-                },
-                groups[3].to_string(),
-            ));
-        }
-    }
-    Err(From::from(format!(
-        "Expected a decimal integer (with optional + or - prefix) and \
-         (optional suffix) argument to {}, but got `{}'",
-        option_name, value_as_string
-    )))
+    match split_comparable_value(value_as_string) {
+        Some((sign, digits, suffix)) => Ok((
+            to_comparable_value(option_name, (sign, digits), value_as_string)?,
+            suffix.to_string(),
+        )),
+        None => Err(From::from(format!(
+            "Expected a decimal integer (with optional + or - prefix) and \
+             (optional suffix) argument to {}, but got `{}'",
+            option_name, value_as_string
+        ))),
+    }
 }
 
 /// The main "translate command-line args into a matcher" function. Will call
@@ -204,6 +358,7 @@ fn build_matcher_tree(
     let mut i = arg_index;
     let mut invert_next_matcher = false;
     while i < args.len() {
+        let predicate_index = i;
         let possible_submatcher = match args[i] {
             "-print" => Some(printer::Printer::new_box()),
             "-true" => Some(logical_matchers::TrueMatcher::new_box()),
@@ -213,14 +368,57 @@ fn build_matcher_tree(
                     return Err(From::from(format!("missing argument to {}", args[i])));
                 }
                 i += 1;
-                Some(name::NameMatcher::new_box(args[i])?)
+                if config.ignore_case {
+                    Some(name::CaselessNameMatcher::new_box(
+                        args[i],
+                        config.brace_expansion,
+                    )?)
+                } else {
+                    Some(name::NameMatcher::new_box(args[i], config.brace_expansion)?)
+                }
             }
             "-iname" => {
                 if i >= args.len() - 1 {
                     return Err(From::from(format!("missing argument to {}", args[i])));
                 }
                 i += 1;
-                Some(name::CaselessNameMatcher::new_box(args[i])?)
+                Some(name::CaselessNameMatcher::new_box(
+                    args[i],
+                    config.brace_expansion,
+                )?)
+            }
+            "-regex" | "-iregex" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                i += 1;
+                Some(
+                    regex_matcher::RegexMatcher::new_box(
+                        config.regex_type,
+                        config.regex_anchor,
+                        args[i],
+                        config.ignore_case || args[i - 1] == "-iregex",
+                    )
+                    .map_err(|e| format!("{}: {}", args[i - 1], e))?,
+                )
+            }
+            "-regextype" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                // only affects the -regex/-iregex tests that come after it
+                config.regex_type = args[i + 1].parse()?;
+                i += 1;
+                None
+            }
+            "-regex-anchor" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                // like -regextype, only affects the tests that come after it
+                config.regex_anchor = args[i + 1].parse()?;
+                i += 1;
+                None
             }
             "-type" => {
                 if i >= args.len() - 1 {
@@ -229,17 +427,57 @@ fn build_matcher_tree(
                 i += 1;
                 Some(type_matcher::TypeMatcher::new_box(args[i])?)
             }
+            "-empty" => Some(empty::EmptyMatcher::new_box()),
+            "-readable" => Some(access::AccessMatcher::new_box(
+                access::AccessType::Readable,
+            )?),
+            "-writable" => Some(access::AccessMatcher::new_box(
+                access::AccessType::Writable,
+            )?),
+            "-executable" => Some(access::AccessMatcher::new_box(
+                access::AccessType::Executable,
+            )?),
             "-delete" => {
                 // -delete implicitly requires -depth
                 config.depth_first = true;
-                Some(delete::DeleteMatcher::new_box()?)
+                Some(delete::DeleteMatcher::new_box(config.delete_stats)?)
             }
             "-newer" => {
                 if i >= args.len() - 1 {
                     return Err(From::from(format!("missing argument to {}", args[i])));
                 }
                 i += 1;
-                Some(time::NewerMatcher::new_box(args[i])?)
+                Some(time::NewerMatcher::new_box_with_stat(args[i], |path| {
+                    config.reference_metadata(path, true)
+                })?)
+            }
+            "-samefile" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                i += 1;
+                Some(samefile::SameFileMatcher::new_box_with_stat(
+                    args[i],
+                    |path| config.reference_metadata(path, false),
+                )?)
+            }
+            s if s.starts_with("-newer") && s.len() == "-newerXY".len() => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                i += 1;
+                Some(time::NewerMatcher::new_box_xy_with_stat(
+                    args[i - 1],
+                    args[i],
+                    |path| config.reference_metadata(path, true),
+                )?)
+            }
+            "-within" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                i += 1;
+                Some(time::WithinMatcher::new_box(args[i])?)
             }
             "-mtime" | "-atime" | "-ctime" => {
                 if i >= args.len() - 1 {
@@ -257,14 +495,30 @@ fn build_matcher_tree(
                 i += 1;
                 Some(time::FileTimeMatcher::new_box(file_time_type, days))
             }
-            "-size" => {
+            "-size" | "-size-disk" => {
                 if i >= args.len() - 1 {
                     return Err(From::from(format!("missing argument to {}", args[i])));
                 }
+                // a fractional part (as in 1.5M) is left in the suffix, and so
+                // is rejected along with any other invalid unit
                 let (size, unit) =
                     convert_arg_to_comparable_value_and_suffix(args[i], args[i + 1])?;
                 i += 1;
-                Some(size::SizeMatcher::new_box(size, &unit)?)
+                if args[i - 1] == "-size" {
+                    Some(size::SizeMatcher::new_box(size, &unit)?)
+                } else {
+                    Some(size::SizeMatcher::new_disk_usage_box(size, &unit)?)
+                }
+            }
+            "-sizelike" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                i += 1;
+                Some(size::SizeMatcher::new_like_box_with_stat(
+                    args[i],
+                    |path| config.reference_metadata(path, false),
+                )?)
             }
             "-exec" | "-execdir" => {
                 let mut arg_index = i + 1;
@@ -293,6 +547,13 @@ fn build_matcher_tree(
                     expression == "-execdir",
                 )?)
             }
+            "-execsh" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                i += 1;
+                Some(exec::ShellExecMatcher::new_box(args[i])?)
+            }
             "-perm" => {
                 if i >= args.len() - 1 {
                     return Err(From::from(format!("missing argument to {}", args[i])));
@@ -301,6 +562,16 @@ fn build_matcher_tree(
                 Some(perm::PermMatcher::new_box(args[i])?)
             }
             "-prune" => Some(prune::PruneMatcher::new_box()),
+            "-count" => Some(count::CountMatcher::new_box()),
+            "-newest" | "-oldest" => Some(newest::NewestMatcher::new_box(args[i] == "-oldest")),
+            "-limit" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                let limit = convert_arg_to_number(args[i], args[i + 1])?;
+                i += 1;
+                Some(limit::LimitMatcher::new_box(limit))
+            }
             "-not" | "!" => {
                 if !are_more_expressions(args, i) {
                     return Err(From::from(format!(
@@ -311,13 +582,14 @@ fn build_matcher_tree(
                 invert_next_matcher = !invert_next_matcher;
                 None
             }
-            "-a" => {
+            "-a" | "-and" => {
                 if !are_more_expressions(args, i) {
                     return Err(From::from(format!(
                         "expected an expression after {}",
                         args[i]
                     )));
                 }
+                check_not_after_negation(args[i], invert_next_matcher)?;
                 top_level_matcher.check_new_and_condition()?;
                 None
             }
@@ -328,6 +600,7 @@ fn build_matcher_tree(
                         args[i]
                     )));
                 }
+                check_not_after_negation(args[i], invert_next_matcher)?;
                 top_level_matcher.new_or_condition(args[i])?;
                 None
             }
@@ -338,10 +611,16 @@ fn build_matcher_tree(
                         args[i]
                     )));
                 }
+                check_not_after_negation(args[i], invert_next_matcher)?;
                 top_level_matcher.new_list_condition()?;
                 None
             }
             "(" => {
+                if i + 1 < args.len() && args[i + 1] == ")" {
+                    return Err(From::from(
+                        "invalid expression; empty parentheses are not allowed.",
+                    ));
+                }
                 let (new_arg_index, sub_matcher) = build_matcher_tree(args, config, i + 1, true)?;
                 i = new_arg_index;
                 Some(sub_matcher)
@@ -357,11 +636,41 @@ fn build_matcher_tree(
                 config.depth_first = true;
                 None
             }
+            "-xdev" | "-mount" => {
+                // TODO add warning if it appears after actual testing criterion
+                config.same_file_system = true;
+                None
+            }
             "-sorted" => {
                 // TODO add warning if it appears after actual testing criterion
                 config.sorted_output = true;
                 None
             }
+            "-sorted-reverse" => {
+                // TODO add warning if it appears after actual testing criterion
+                config.reverse_sorted_output = true;
+                None
+            }
+            "-stop-on-error" => {
+                config.stop_on_error = true;
+                None
+            }
+            "-color" | "-color=auto" => {
+                config.ls_colors = if config.output_is_terminal {
+                    Some(Rc::new(LsColors::from_env()))
+                } else {
+                    None
+                };
+                None
+            }
+            "-color=always" => {
+                config.ls_colors = Some(Rc::new(LsColors::from_env()));
+                None
+            }
+            "-color=never" => {
+                config.ls_colors = None;
+                None
+            }
             "-maxdepth" => {
                 if i >= args.len() - 1 {
                     return Err(From::from(format!("missing argument to {}", args[i])));
@@ -378,6 +687,18 @@ fn build_matcher_tree(
                 i += 1;
                 None
             }
+            "-exec-timeout" => {
+                if i >= args.len() - 1 {
+                    return Err(From::from(format!("missing argument to {}", args[i])));
+                }
+                // like timeout(1), 0 means no limit at all
+                config.exec_timeout = match convert_arg_to_number(args[i], args[i + 1])? {
+                    0 => None,
+                    seconds => Some(Duration::from_secs(seconds as u64)),
+                };
+                i += 1;
+                None
+            }
             "-help" | "--help" => {
                 config.help_requested = true;
                 None
@@ -385,7 +706,11 @@ fn build_matcher_tree(
 
             _ => return Err(From::from(format!("Unrecognized flag: '{}'", args[i]))),
         };
-        if let Some(submatcher) = possible_submatcher {
+        if let Some(mut submatcher) = possible_submatcher {
+            if config.debug_rates {
+                let predicate = args[predicate_index..=i].join(" ");
+                submatcher = rates::RatesMatcher::new_box(&predicate, submatcher);
+            }
             if invert_next_matcher {
                 top_level_matcher
                     .new_and_condition(logical_matchers::NotMatcher::new_box(submatcher));
@@ -426,6 +751,27 @@ mod tests {
         panic!("Couldn't find {} in {}", filename, directory);
     }
 
+    /// Creates test_data/links/link-f and link-d, pointing at abbbc and subdir.
+    /// git does not translate links (in test_data) to Windows links, so
+    /// rather than checking them in, tests that need them create them here.
+    pub fn create_test_links() {
+        #[cfg(unix)]
+        use std::os::unix::fs::{symlink as symlink_dir, symlink as symlink_file};
+        #[cfg(windows)]
+        use std::os::windows::fs::{symlink_dir, symlink_file};
+
+        for result in [
+            symlink_file("abbbc", "test_data/links/link-f"),
+            symlink_dir("subdir", "test_data/links/link-d"),
+        ] {
+            if let Err(e) = result {
+                if e.kind() != std::io::ErrorKind::AlreadyExists {
+                    panic!("Failed to create sym link: {:?}", e);
+                }
+            }
+        }
+    }
+
     #[test]
     fn build_top_level_matcher_name() {
         let abbbc_lower = get_dir_entry_for("./test_data/simple", "abbbc");
@@ -650,6 +996,116 @@ mod tests {
         );
     }
 
+    /// Returns the (sorted) names of the files under test_data/simple that
+    /// match the given expression.
+    fn names_matching(args: &[&str]) -> Vec<String> {
+        let mut config = Config::default();
+        let deps = FakeDependencies::new();
+        let matcher = build_top_level_matcher(args, &mut config).unwrap();
+        let mut names = vec![];
+        for entry in WalkDir::new(fix_up_slashes("./test_data/simple")) {
+            let entry = entry.unwrap();
+            if matcher.matches(&entry, &mut deps.new_matcher_io()) {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn build_top_level_matcher_precedence() {
+        let cases: &[(&[&str], &[&str])] = &[
+            // -a binds more tightly than -o: a OR (b AND c)
+            (
+                &[
+                    "-name", "abbbc", "-o", "-name", "subdir", "-a", "-name", "x",
+                ],
+                &["abbbc"],
+            ),
+            // ...and (a AND b) OR c
+            (
+                &[
+                    "-name", "x", "-a", "-name", "abbbc", "-o", "-name", "subdir",
+                ],
+                &["subdir"],
+            ),
+            // the implicit -a has the same precedence as the explicit one
+            (
+                &["-name", "a*", "-name", "*c", "-o", "-name", "s*"],
+                &["abbbc", "simple", "subdir"],
+            ),
+            (&["-name", "abbbc", "-and", "-true"], &["abbbc"]),
+            // ! binds more tightly than both: (NOT a) OR b
+            (
+                &["!", "-name", "abbbc", "-o", "-name", "subdir"],
+                &["ABBBC", "simple", "subdir"],
+            ),
+            (
+                &["-not", "-name", "abbbc", "-a", "-name", "s*"],
+                &["simple", "subdir"],
+            ),
+            // ...unless the operand is bracketed
+            (
+                &["!", "(", "-name", "abbbc", "-o", "-name", "subdir", ")"],
+                &["ABBBC", "simple"],
+            ),
+            (
+                &[
+                    "-name", "x", "-a", "(", "-name", "abbbc", "-o", "-true", ")",
+                ],
+                &[],
+            ),
+            (
+                &["(", "(", "(", "-name", "abbbc", ")", ")", ")"],
+                &["abbbc"],
+            ),
+            (
+                &[
+                    "-name", "*b*", "-a", "(", "-name", "s*", "-o", "(", "!", "-iname", "ABBBC",
+                    ")", ")",
+                ],
+                &["subdir"],
+            ),
+            (
+                &[
+                    "-name", "abbbc", "-o", "-name", "subdir", "-o", "(", "-name", "simple", "-a",
+                    "-false", ")",
+                ],
+                &["abbbc", "subdir"],
+            ),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(&names_matching(args), expected, "for {:?}", args);
+        }
+    }
+
+    #[test]
+    fn build_top_level_matcher_bad_operator_placement() {
+        for args in &[
+            &["-true", "!", "-o", "-true"][..],
+            &["-true", "!", "-a", "-true"],
+            &["-true", "-not", ",", "-true"],
+        ] {
+            let mut config = Config::default();
+            match build_top_level_matcher(args, &mut config) {
+                Err(e) => assert!(
+                    e.to_string().contains("you have used a binary operator"),
+                    "bad description for {:?}: {}",
+                    args,
+                    e
+                ),
+                Ok(_) => panic!("{:?} should fail", args),
+            }
+        }
+
+        let mut config = Config::default();
+        match build_top_level_matcher(&["(", ")"], &mut config) {
+            Err(e) => assert!(e.to_string().contains("empty parentheses")),
+            Ok(_) => panic!("empty parentheses should fail"),
+        }
+    }
+
     #[test]
     fn build_top_level_matcher_list_works() {
         let abbbc = get_dir_entry_for("./test_data/simple", "abbbc");
@@ -711,7 +1167,7 @@ mod tests {
     fn build_top_level_matcher_too_many_brackets() {
         let mut config = Config::default();
 
-        if let Err(e) = build_top_level_matcher(&["-true", "(", ")", ")"], &mut config) {
+        if let Err(e) = build_top_level_matcher(&["-true", "(", "-true", ")", ")"], &mut config) {
             assert!(e.to_string().contains("too many ')'"));
         } else {
             panic!("parsing argument list with too many closing brackets should fail");
@@ -774,15 +1230,15 @@ mod tests {
             "0 should not be less than 0"
         );
         assert!(
-            ComparableValue::LessThan(u64::max_value()).matches(0),
+            ComparableValue::LessThan(u64::MAX).matches(0),
             "0 should be less than max_value"
         );
         assert!(
-            !ComparableValue::LessThan(0).matches(u64::max_value()),
+            !ComparableValue::LessThan(0).matches(u64::MAX),
             "max_value should not be less than 0"
         );
         assert!(
-            !ComparableValue::LessThan(u64::max_value()).matches(u64::max_value()),
+            !ComparableValue::LessThan(u64::MAX).matches(u64::MAX),
             "max_value should not be less than max_value"
         );
 
@@ -791,15 +1247,15 @@ mod tests {
             "0 should be equal to 0"
         );
         assert!(
-            !ComparableValue::EqualTo(u64::max_value()).matches(0),
+            !ComparableValue::EqualTo(u64::MAX).matches(0),
             "0 should not be equal to max_value"
         );
         assert!(
-            !ComparableValue::EqualTo(0).matches(u64::max_value()),
+            !ComparableValue::EqualTo(0).matches(u64::MAX),
             "max_value should not be equal to 0"
         );
         assert!(
-            ComparableValue::EqualTo(u64::max_value()).matches(u64::max_value()),
+            ComparableValue::EqualTo(u64::MAX).matches(u64::MAX),
             "max_value should be equal to max_value"
         );
 
@@ -808,15 +1264,15 @@ mod tests {
             "0 should not be more than 0"
         );
         assert!(
-            !ComparableValue::MoreThan(u64::max_value()).matches(0),
+            !ComparableValue::MoreThan(u64::MAX).matches(0),
             "0 should not be more than max_value"
         );
         assert!(
-            ComparableValue::MoreThan(0).matches(u64::max_value()),
+            ComparableValue::MoreThan(0).matches(u64::MAX),
             "max_value should be more than 0"
         );
         assert!(
-            !ComparableValue::MoreThan(u64::max_value()).matches(u64::max_value()),
+            !ComparableValue::MoreThan(u64::MAX).matches(u64::MAX),
             "max_value should not be more than max_value"
         );
     }
@@ -828,23 +1284,23 @@ mod tests {
             "0 should not be less than 0"
         );
         assert!(
-            ComparableValue::LessThan(u64::max_value()).imatches(0),
+            ComparableValue::LessThan(u64::MAX).imatches(0),
             "0 should be less than max_value"
         );
         assert!(
-            !ComparableValue::LessThan(0).imatches(i64::max_value()),
+            !ComparableValue::LessThan(0).imatches(i64::MAX),
             "max_value should not be less than 0"
         );
         assert!(
-            ComparableValue::LessThan(u64::max_value()).imatches(i64::max_value()),
+            ComparableValue::LessThan(u64::MAX).imatches(i64::MAX),
             "max_value should be less than max_value"
         );
         assert!(
-            ComparableValue::LessThan(0).imatches(i64::min_value()),
+            ComparableValue::LessThan(0).imatches(i64::MIN),
             "min_value should be less than 0"
         );
         assert!(
-            ComparableValue::LessThan(u64::max_value()).imatches(i64::min_value()),
+            ComparableValue::LessThan(u64::MAX).imatches(i64::MIN),
             "min_value should be less than max_value"
         );
 
@@ -853,27 +1309,27 @@ mod tests {
             "0 should be equal to 0"
         );
         assert!(
-            !ComparableValue::EqualTo(u64::max_value()).imatches(0),
+            !ComparableValue::EqualTo(u64::MAX).imatches(0),
             "0 should not be equal to max_value"
         );
         assert!(
-            !ComparableValue::EqualTo(0).imatches(i64::max_value()),
+            !ComparableValue::EqualTo(0).imatches(i64::MAX),
             "max_value should not be equal to 0"
         );
         assert!(
-            !ComparableValue::EqualTo(u64::max_value()).imatches(i64::max_value()),
+            !ComparableValue::EqualTo(u64::MAX).imatches(i64::MAX),
             "max_value should not be equal to i64::max_value"
         );
         assert!(
-            ComparableValue::EqualTo(i64::max_value() as u64).imatches(i64::max_value()),
+            ComparableValue::EqualTo(i64::MAX as u64).imatches(i64::MAX),
             "i64::max_value should be equal to i64::max_value"
         );
         assert!(
-            !ComparableValue::EqualTo(0).imatches(i64::min_value()),
+            !ComparableValue::EqualTo(0).imatches(i64::MIN),
             "min_value should not be equal to 0"
         );
         assert!(
-            !ComparableValue::EqualTo(u64::max_value()).imatches(i64::min_value()),
+            !ComparableValue::EqualTo(u64::MAX).imatches(i64::MIN),
             "min_value should not be equal to max_value"
         );
 
@@ -882,23 +1338,23 @@ mod tests {
             "0 should not be more than 0"
         );
         assert!(
-            !ComparableValue::MoreThan(u64::max_value()).imatches(0),
+            !ComparableValue::MoreThan(u64::MAX).imatches(0),
             "0 should not be more than max_value"
         );
         assert!(
-            ComparableValue::MoreThan(0).imatches(i64::max_value()),
+            ComparableValue::MoreThan(0).imatches(i64::MAX),
             "max_value should be more than 0"
         );
         assert!(
-            !ComparableValue::MoreThan(u64::max_value()).imatches(i64::max_value()),
+            !ComparableValue::MoreThan(u64::MAX).imatches(i64::MAX),
             "max_value should not be more than max_value"
         );
         assert!(
-            !ComparableValue::MoreThan(0).imatches(i64::min_value()),
+            !ComparableValue::MoreThan(0).imatches(i64::MIN),
             "min_value should not be more than 0"
         );
         assert!(
-            !ComparableValue::MoreThan(u64::max_value()).imatches(i64::min_value()),
+            !ComparableValue::MoreThan(u64::MAX).imatches(i64::MIN),
             "min_value should not be more than max_value"
         );
     }
@@ -918,6 +1374,278 @@ mod tests {
         }
     }
 
+    #[test]
+    fn build_top_level_matcher_newer_xy() {
+        let mut config = Config::default();
+        assert!(build_top_level_matcher(&["-newermt", "2000-01-01"], &mut config).is_ok());
+        assert!(build_top_level_matcher(&["-newerBt", "2000-01-01"], &mut config).is_ok());
+
+        match build_top_level_matcher(&["-newertm", "2000-01-01"], &mut config) {
+            Err(e) => assert!(e.to_string().contains("only valid as the second letter")),
+            Ok(_) => panic!("-newertm should fail"),
+        }
+        match build_top_level_matcher(&["-newermt"], &mut config) {
+            Err(e) => assert!(e.to_string().contains("missing argument to -newermt")),
+            Ok(_) => panic!("-newermt without an argument should fail"),
+        }
+    }
+
+    #[test]
+    fn build_top_level_matcher_shares_newer_references() {
+        use std::fs::File;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("build_top_level_matcher_shares_newer_references").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        File::create(temp_dir.path().join("newFile")).expect("create temp file");
+        let new_file = get_dir_entry_for(&temp_dir_path, "newFile");
+        let old_file = get_dir_entry_for("./test_data/simple", "abbbc");
+        let reference = fix_up_slashes("./test_data/simple/subdir/ABBBC");
+        let deps = FakeDependencies::new();
+
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &[
+                "-newer", &reference, "-newermm", &reference, "-newercm", &reference,
+            ],
+            &mut config,
+        )
+        .unwrap();
+        assert_eq!(config.reference_metadata.len(), 1);
+        assert!(matcher.matches(&new_file, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&old_file, &mut deps.new_matcher_io()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_top_level_matcher_sizelike_symlink_reference() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("build_top_level_matcher_sizelike").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        fs::write(temp_dir.path().join("target"), "0123456789").unwrap();
+        symlink("target", temp_dir.path().join("link")).unwrap();
+        let target = get_dir_entry_for(&temp_dir_path, "target");
+        let link = get_dir_entry_for(&temp_dir_path, "link");
+        let reference = temp_dir.path().join("link").to_string_lossy().to_string();
+        let deps = FakeDependencies::new();
+
+        // the reference is the link itself, not what it points to, and is
+        // only stat'd once
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &["-sizelike", &reference, "-sizelike", &reference],
+            &mut config,
+        )
+        .unwrap();
+        assert_eq!(config.reference_metadata.len(), 1);
+        assert!(matcher.matches(&link, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&target, &mut deps.new_matcher_io()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_top_level_matcher_samefile_shares_reference() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("build_top_level_matcher_samefile").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        fs::write(temp_dir.path().join("target"), "0123456789").unwrap();
+        symlink("target", temp_dir.path().join("link")).unwrap();
+        let target = get_dir_entry_for(&temp_dir_path, "target");
+        let link = get_dir_entry_for(&temp_dir_path, "link");
+        let reference = temp_dir.path().join("link").to_string_lossy().to_string();
+        let deps = FakeDependencies::new();
+
+        // -samefile and -sizelike both see the link itself, from one stat
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &["-samefile", &reference, "-sizelike", &reference],
+            &mut config,
+        )
+        .unwrap();
+        assert_eq!(config.reference_metadata.len(), 1);
+        assert!(matcher.matches(&link, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&target, &mut deps.new_matcher_io()));
+
+        let mut config = Config::default();
+        assert!(build_top_level_matcher(
+            &["-samefile", &format!("{}/missing", temp_dir_path)],
+            &mut config
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn build_top_level_matcher_regextype_is_positional() {
+        let abbbc = get_dir_entry_for("./test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+
+        // in the default flavour + is special, but in sed's it needs a
+        // backslash
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &[
+                "-regex",
+                ".*/ab+c",
+                "-regextype",
+                "sed",
+                "-regex",
+                r".*/ab\+c",
+            ],
+            &mut config,
+        )
+        .unwrap();
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &[
+                "-regex",
+                ".*/ab+c",
+                "-regextype",
+                "sed",
+                "-regex",
+                ".*/ab+c",
+            ],
+            &mut config,
+        )
+        .unwrap();
+        assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+
+        // "default" switches back to the flavour we started with
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &[
+                "-regextype",
+                "sed",
+                "-regextype",
+                "default",
+                "-regex",
+                ".*/ab+c",
+            ],
+            &mut config,
+        )
+        .unwrap();
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        assert_eq!(config.regex_type, RegexType::default());
+
+        let mut config = Config::default();
+        if let Err(e) = build_top_level_matcher(&["-regextype", "perl"], &mut config) {
+            assert!(e.to_string().contains("Unknown regular expression type"));
+        } else {
+            panic!("-regextype perl should fail");
+        }
+    }
+
+    #[test]
+    fn build_top_level_matcher_regex_anchor() {
+        let abbbc = get_dir_entry_for("./test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+
+        // only the -regex tests after -regex-anchor are affected
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &[
+                "-regex",
+                ".*bbb.*",
+                "-regex-anchor",
+                "substring",
+                "-regex",
+                "bbb",
+            ],
+            &mut config,
+        )
+        .unwrap();
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        assert_eq!(config.regex_anchor, RegexAnchor::Substring);
+
+        let mut config = Config::default();
+        let matcher = build_top_level_matcher(
+            &["-regex", "bbb", "-regex-anchor", "substring"],
+            &mut config,
+        )
+        .unwrap();
+        assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+
+        let mut config = Config::default();
+        if let Err(e) = build_top_level_matcher(&["-regex-anchor", "middle"], &mut config) {
+            assert!(e.to_string().contains("Unknown -regex-anchor 'middle'"));
+        } else {
+            panic!("-regex-anchor middle should fail");
+        }
+    }
+
+    #[test]
+    fn build_top_level_matcher_bad_size_value() {
+        let mut config = Config::default();
+
+        match build_top_level_matcher(&["-size", "1.5M"], &mut config) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "invalid -size type '.5M'. Only allowed values are <nothing>, \
+                 b, c, w, k, M or G"
+            ),
+            Ok(_) => panic!("parsing a fractional size should fail"),
+        }
+
+        match build_top_level_matcher(&["-size", "1Z"], &mut config) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "invalid -size type 'Z'. Only allowed values are <nothing>, \
+                 b, c, w, k, M or G"
+            ),
+            Ok(_) => panic!("parsing a size with an unknown unit should fail"),
+        }
+
+        if let Err(e) = build_top_level_matcher(&["-size", "x1k"], &mut config) {
+            assert!(
+                e.to_string().contains("Expected a decimal integer"),
+                "bad description: {}",
+                e
+            );
+        } else {
+            panic!("parsing a size with leading garbage should fail");
+        }
+
+        match build_top_level_matcher(&["-size", "+99999999999999999999999k"], &mut config) {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "argument `+99999999999999999999999k' to -size is too large: the \
+                 maximum is 18446744073709551615"
+            ),
+            Ok(_) => panic!("parsing a size that doesn't fit in 64 bits should fail"),
+        }
+    }
+
+    #[test]
+    fn build_top_level_matcher_numbers_too_large() {
+        let mut config = Config::default();
+        for args in &[
+            ["-mtime", "-99999999999999999999999"],
+            ["-maxdepth", "99999999999999999999999"],
+            ["-limit", "99999999999999999999999"],
+        ] {
+            match build_top_level_matcher(args, &mut config) {
+                Err(e) => assert!(
+                    e.to_string()
+                        .contains(&format!("to {} is too large", args[0])),
+                    "bad description: {}",
+                    e
+                ),
+                Ok(_) => panic!("{} {} should fail", args[0], args[1]),
+            }
+        }
+
+        // values that do fit are fine
+        assert!(build_top_level_matcher(&["-size", "18446744073709551615c"], &mut config).is_ok());
+        assert!(build_top_level_matcher(&["-maxdepth", "-1"], &mut config).is_err());
+    }
+
     #[test]
     fn build_top_level_exec_not_enough_args() {
         let mut config = Config::default();
@@ -1001,4 +1729,21 @@ mod tests {
             panic!("-perm with no mode pattern should fail");
         }
     }
+
+    #[test]
+    fn build_top_level_matcher_color_auto() {
+        for &arg in &["-color", "-color=auto"] {
+            // colors are only used when printing to a terminal
+            let mut config = Config::default();
+            build_top_level_matcher(&[arg], &mut config).unwrap();
+            assert!(config.ls_colors.is_none());
+
+            let mut config = Config {
+                output_is_terminal: true,
+                ..Config::default()
+            };
+            build_top_level_matcher(&[arg], &mut config).unwrap();
+            assert!(config.ls_colors.is_some());
+        }
+    }
 }
@@ -10,63 +10,188 @@ use walkdir::DirEntry;
 
 use super::{Matcher, MatcherIO};
 
+/// Finds the '}' that closes the '{' at `open`, along with the positions of
+/// the commas that separate its alternatives (but not those of any nested
+/// braces).
+fn find_closing_brace(pattern: &[u8], open: usize) -> Option<(usize, Vec<usize>)> {
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut i = open;
+    while i < pattern.len() {
+        match pattern[i] {
+            b'\\' => i += 1,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((i, commas));
+                }
+            }
+            b',' if depth == 1 => commas.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Removes the backslashes from escaped braces, commas and backslashes once
+/// there's nothing left to expand. glob::Pattern would take them literally.
+fn unescape_braces(pattern: &str) -> String {
+    let mut unescaped = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if matches!(next, ',' | '{' | '}' | '\\') => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Expands the shell-style alternatives in `pattern`, as bash does, so that
+/// "{a,b}.txt" becomes "a.txt" and "b.txt". Braces can be nested, and ones
+/// without a comma (or without a partner) are left alone. A backslash
+/// escapes a brace or comma.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let bytes = pattern.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'{' => {
+                if let Some((close, commas)) = find_closing_brace(bytes, i) {
+                    if !commas.is_empty() {
+                        let (prefix, suffix) = (&pattern[..i], &pattern[close + 1..]);
+                        let mut starts = vec![i + 1];
+                        starts.extend(commas.iter().map(|comma| comma + 1));
+                        let mut ends = commas;
+                        ends.push(close);
+                        return starts
+                            .into_iter()
+                            .zip(ends)
+                            .flat_map(|(start, end)| {
+                                expand_braces(&format!(
+                                    "{}{}{}",
+                                    prefix,
+                                    &pattern[start..end],
+                                    suffix
+                                ))
+                            })
+                            .collect();
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    vec![unescape_braces(pattern)]
+}
+
+fn compile_patterns(
+    pattern_string: &str,
+    brace_expansion: bool,
+) -> Result<Vec<Pattern>, PatternError> {
+    if brace_expansion {
+        expand_braces(pattern_string)
+            .iter()
+            .map(|pattern| Pattern::new(pattern))
+            .collect()
+    } else {
+        Ok(vec![Pattern::new(pattern_string)?])
+    }
+}
+
 /// This matcher makes a case-sensitive comparison of the name against a
 /// shell wildcard pattern. See `glob::Pattern` for details on the exact
-/// syntax.
+/// syntax. With the non-standard --brace-expansion, the pattern can also
+/// contain {alternatives,like,this}, and the name has to match any one of
+/// them.
 pub struct NameMatcher {
-    pattern: Pattern,
+    patterns: Vec<Pattern>,
 }
 
 impl NameMatcher {
+    #[cfg(test)]
     pub fn new(pattern_string: &str) -> Result<NameMatcher, PatternError> {
-        let p = Pattern::new(pattern_string)?;
-        Ok(NameMatcher { pattern: p })
+        NameMatcher::new_with_braces(pattern_string, false)
     }
 
-    pub fn new_box(pattern_string: &str) -> Result<Box<dyn Matcher>, PatternError> {
-        Ok(Box::new(NameMatcher::new(pattern_string)?))
+    pub fn new_with_braces(
+        pattern_string: &str,
+        brace_expansion: bool,
+    ) -> Result<NameMatcher, PatternError> {
+        Ok(NameMatcher {
+            patterns: compile_patterns(pattern_string, brace_expansion)?,
+        })
+    }
+
+    pub fn new_box(
+        pattern_string: &str,
+        brace_expansion: bool,
+    ) -> Result<Box<dyn Matcher>, PatternError> {
+        Ok(Box::new(NameMatcher::new_with_braces(
+            pattern_string,
+            brace_expansion,
+        )?))
     }
 }
 
 impl Matcher for NameMatcher {
+    // file_name() is the final path component exactly as the OS returned it
+    // from readdir, so there's no Windows-style normalization of trailing
+    // dots/spaces or reserved names to worry about here.
     fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
-        self.pattern
-            .matches(file_info.file_name().to_string_lossy().as_ref())
+        let name = file_info.file_name().to_string_lossy();
+        self.patterns.iter().any(|pattern| pattern.matches(&name))
     }
 }
 
 /// This matcher makes a case-insensitive comparison of the name against a
 /// shell wildcard pattern. See `glob::Pattern` for details on the exact
-/// syntax.
+/// syntax, and `NameMatcher` for brace expansion.
 pub struct CaselessNameMatcher {
-    pattern: Pattern,
+    patterns: Vec<Pattern>,
 }
 
 impl CaselessNameMatcher {
+    #[cfg(test)]
     pub fn new(pattern_string: &str) -> Result<CaselessNameMatcher, PatternError> {
-        let p = Pattern::new(&pattern_string.to_lowercase())?;
-        Ok(CaselessNameMatcher { pattern: p })
+        CaselessNameMatcher::new_with_braces(pattern_string, false)
+    }
+
+    pub fn new_with_braces(
+        pattern_string: &str,
+        brace_expansion: bool,
+    ) -> Result<CaselessNameMatcher, PatternError> {
+        Ok(CaselessNameMatcher {
+            patterns: compile_patterns(&pattern_string.to_lowercase(), brace_expansion)?,
+        })
     }
 
-    pub fn new_box(pattern_string: &str) -> Result<Box<dyn Matcher>, PatternError> {
-        Ok(Box::new(CaselessNameMatcher::new(pattern_string)?))
+    pub fn new_box(
+        pattern_string: &str,
+        brace_expansion: bool,
+    ) -> Result<Box<dyn Matcher>, PatternError> {
+        Ok(Box::new(CaselessNameMatcher::new_with_braces(
+            pattern_string,
+            brace_expansion,
+        )?))
     }
 }
 
 impl super::Matcher for CaselessNameMatcher {
     fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
-        self.pattern.matches(
-            file_info
-                .file_name()
-                .to_string_lossy()
-                .to_lowercase()
-                .as_ref(),
-        )
+        let name = file_info.file_name().to_string_lossy().to_lowercase();
+        self.patterns.iter().any(|pattern| pattern.matches(&name))
     }
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
     use crate::find::matchers::tests::get_dir_entry_for;
@@ -76,7 +201,7 @@ mod tests {
     #[test]
     fn matching_with_wrong_case_returns_false() {
         let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
-        let matcher = NameMatcher::new(&"A*C".to_string()).unwrap();
+        let matcher = NameMatcher::new("A*C").unwrap();
         let deps = FakeDependencies::new();
         assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
     }
@@ -84,7 +209,7 @@ mod tests {
     #[test]
     fn matching_with_right_case_returns_true() {
         let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
-        let matcher = NameMatcher::new(&"abb?c".to_string()).unwrap();
+        let matcher = NameMatcher::new("abb?c").unwrap();
         let deps = FakeDependencies::new();
         assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
     }
@@ -92,21 +217,47 @@ mod tests {
     #[test]
     fn not_matching_returns_false() {
         let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
-        let matcher = NameMatcher::new(&"shouldn't match".to_string()).unwrap();
+        let matcher = NameMatcher::new("shouldn't match").unwrap();
         let deps = FakeDependencies::new();
         assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
     }
 
+    #[cfg(windows)]
+    #[test]
+    fn matching_trailing_dot_and_space() {
+        use std::fs::File;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("matching_trailing_dot_and_space").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        // Win32 path normalization strips trailing dots and spaces, so we
+        // need a verbatim (\\?\) path, as returned by canonicalize(), to
+        // create files with them at all.
+        let verbatim_dir = temp_dir.path().canonicalize().unwrap();
+        for name in &["dot.", "space "] {
+            if File::create(verbatim_dir.join(name)).is_err() {
+                // the file system may not allow it either
+                continue;
+            }
+            let entry = get_dir_entry_for(&temp_dir_path, name);
+            let deps = FakeDependencies::new();
+            let matcher = NameMatcher::new(name).unwrap();
+            assert!(matcher.matches(&entry, &mut deps.new_matcher_io()));
+            let matcher = NameMatcher::new(name.trim_end_matches(&['.', ' '][..])).unwrap();
+            assert!(!matcher.matches(&entry, &mut deps.new_matcher_io()));
+        }
+    }
+
     #[test]
     fn cant_create_with_invalid_pattern() {
-        let result = NameMatcher::new(&"a**c".to_string());
+        let result = NameMatcher::new("a**c");
         assert!(result.is_err());
     }
 
     #[test]
     fn caseless_matching_with_wrong_case_returns_true() {
         let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
-        let matcher = CaselessNameMatcher::new(&"A*C".to_string()).unwrap();
+        let matcher = CaselessNameMatcher::new("A*C").unwrap();
         let deps = FakeDependencies::new();
         assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
     }
@@ -114,7 +265,7 @@ mod tests {
     #[test]
     fn caseless_matching_with_right_case_returns_true() {
         let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
-        let matcher = CaselessNameMatcher::new(&"abb?c".to_string()).unwrap();
+        let matcher = CaselessNameMatcher::new("abb?c").unwrap();
         let deps = FakeDependencies::new();
         assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
     }
@@ -122,14 +273,86 @@ mod tests {
     #[test]
     fn caseless_not_matching_returns_false() {
         let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
-        let matcher = CaselessNameMatcher::new(&"shouldn't match".to_string()).unwrap();
+        let matcher = CaselessNameMatcher::new("shouldn't match").unwrap();
         let deps = FakeDependencies::new();
         assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
     }
 
+    #[test]
+    fn expands_braces() {
+        assert_eq!(expand_braces("{a,b}.txt"), vec!["a.txt", "b.txt"]);
+        assert_eq!(
+            expand_braces("x{a,b{c,d}}y{1,2}"),
+            vec!["xay1", "xay2", "xbcy1", "xbcy2", "xbdy1", "xbdy2"]
+        );
+        assert_eq!(expand_braces("{,.}rc"), vec!["rc", ".rc"]);
+        // nothing to expand
+        assert_eq!(expand_braces("{a}"), vec!["{a}"]);
+        assert_eq!(expand_braces("{a,b"), vec!["{a,b"]);
+        // escapes only stop brace expansion, and don't end up in the patterns
+        assert_eq!(expand_braces(r"\{a,b}"), vec!["{a,b}"]);
+        assert_eq!(expand_braces(r"{a\,b,c}"), vec!["a,b", "c"]);
+        assert_eq!(expand_braces(r"{a\}b,c}"), vec!["a}b", "c"]);
+    }
+
+    #[test]
+    fn matching_with_brace_expansion() {
+        use std::fs::File;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("matching_with_brace_expansion").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        for name in &["a.txt", "b.txt", "c.txt"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        let a = get_dir_entry_for(&temp_dir_path, "a.txt");
+        let b = get_dir_entry_for(&temp_dir_path, "b.txt");
+        let c = get_dir_entry_for(&temp_dir_path, "c.txt");
+        let deps = FakeDependencies::new();
+
+        let matcher = NameMatcher::new_with_braces("{a,b}.txt", true).unwrap();
+        assert!(matcher.matches(&a, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&b, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&c, &mut deps.new_matcher_io()));
+
+        let matcher = CaselessNameMatcher::new_with_braces("{A,B}.TXT", true).unwrap();
+        assert!(matcher.matches(&a, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&b, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&c, &mut deps.new_matcher_io()));
+
+        // without the flag, braces are just characters
+        let matcher = NameMatcher::new("{a,b}.txt").unwrap();
+        assert!(!matcher.matches(&a, &mut deps.new_matcher_io()));
+    }
+
+    #[test]
+    fn matching_escaped_braces_and_commas() {
+        use std::fs::File;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("matching_escaped_braces_and_commas").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        for name in &["a,b", "{a,b}", "c"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        let comma = get_dir_entry_for(&temp_dir_path, "a,b");
+        let braces = get_dir_entry_for(&temp_dir_path, "{a,b}");
+        let c = get_dir_entry_for(&temp_dir_path, "c");
+        let deps = FakeDependencies::new();
+
+        let matcher = NameMatcher::new_with_braces(r"{a\,b,c}", true).unwrap();
+        assert!(matcher.matches(&comma, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&c, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&braces, &mut deps.new_matcher_io()));
+
+        let matcher = NameMatcher::new_with_braces(r"\{a,b}", true).unwrap();
+        assert!(matcher.matches(&braces, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&comma, &mut deps.new_matcher_io()));
+    }
+
     #[test]
     fn caseless_cant_create_with_invalid_pattern() {
-        let result = CaselessNameMatcher::new(&"a**c".to_string());
+        let result = CaselessNameMatcher::new("a**c");
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,134 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::cell::RefCell;
+use std::io::{self, stderr, ErrorKind, Write};
+use std::time::SystemTime;
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+/// Implements the non-standard -newest and -oldest, which instead of
+/// printing each file they're applied to, remember the one with the latest
+/// (or earliest) modification time, and print just that once the search is
+/// over. If several files share that time, the first one found wins.
+pub struct NewestMatcher {
+    oldest: bool,
+    /// The modification time and (already quoted) path of the best file so
+    /// far.
+    best: RefCell<Option<(SystemTime, String)>>,
+}
+
+impl NewestMatcher {
+    pub fn new(oldest: bool) -> NewestMatcher {
+        NewestMatcher {
+            oldest,
+            best: RefCell::new(None),
+        }
+    }
+
+    pub fn new_box(oldest: bool) -> Box<dyn Matcher> {
+        Box::new(NewestMatcher::new(oldest))
+    }
+
+    fn is_better(&self, time: SystemTime) -> bool {
+        match *self.best.borrow() {
+            None => true,
+            Some((best_time, _)) if self.oldest => time < best_time,
+            Some((best_time, _)) => time > best_time,
+        }
+    }
+}
+
+impl Matcher for NewestMatcher {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        let time = match matcher_io
+            .metadata(file_info)
+            .map_err(io::Error::from)
+            .and_then(|metadata| metadata.modified())
+        {
+            Ok(time) => time,
+            Err(e) => {
+                writeln!(
+                    &mut stderr(),
+                    "Error getting modification time for {}: {}",
+                    file_info.path().to_string_lossy(),
+                    e
+                )
+                .unwrap();
+                matcher_io.mark_error();
+                return false;
+            }
+        };
+        if self.is_better(time) {
+            let path = matcher_io
+                .quoting_style()
+                .quote(&file_info.path().to_string_lossy())
+                .to_string();
+            *self.best.borrow_mut() = Some((time, path));
+        }
+        true
+    }
+
+    // like an action, so there's no implicit -print
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+
+    fn finished(&self, matcher_io: &mut MatcherIO) {
+        if let Some((_, ref path)) = *self.best.borrow() {
+            let result = writeln!(matcher_io.deps.get_output().borrow_mut(), "{}", path);
+            match result {
+                Ok(_) => {}
+                Err(ref e) if e.kind() == ErrorKind::BrokenPipe => {}
+                Err(e) => {
+                    writeln!(&mut stderr(), "find: write error: {}", e).unwrap();
+                    matcher_io.mark_error();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::Matcher;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn newest_and_oldest() {
+        let temp_dir = TempDir::new("newest_and_oldest").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        for (name, seconds) in &[("middle", 2000), ("newest", 3000), ("oldest", 1000)] {
+            File::create(temp_dir.path().join(name))
+                .and_then(|f| f.set_modified(UNIX_EPOCH + Duration::from_secs(*seconds)))
+                .unwrap();
+        }
+
+        for &(oldest, expected) in &[(false, "newest"), (true, "oldest")] {
+            let deps = FakeDependencies::new();
+            let matcher = NewestMatcher::new(oldest);
+            assert!(matcher.has_side_effects());
+            for name in &["middle", "newest", "oldest"] {
+                let entry = get_dir_entry_for(&temp_dir_path, name);
+                assert!(matcher.matches(&entry, &mut deps.new_matcher_io()));
+            }
+            assert_eq!(deps.get_output_as_string(), "");
+
+            matcher.finished(&mut deps.new_matcher_io());
+            assert_eq!(
+                deps.get_output_as_string(),
+                format!("{}/{}\n", temp_dir_path, expected)
+            );
+        }
+    }
+}
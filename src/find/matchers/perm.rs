@@ -292,9 +292,9 @@ impl PermMatcher {
 
 impl Matcher for PermMatcher {
     #[cfg(unix)]
-    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
         use std::os::unix::fs::PermissionsExt;
-        match file_info.metadata() {
+        match matcher_io.metadata(file_info) {
             Ok(metadata) => self
                 .comparison_type
                 .mode_bits_match(self.pattern, metadata.permissions().mode()),
@@ -306,6 +306,7 @@ impl Matcher for PermMatcher {
                     e
                 )
                 .unwrap();
+                matcher_io.mark_error();
                 false
             }
         }
@@ -327,7 +328,7 @@ impl Matcher for PermMatcher {
 mod tests {
     use super::parsing;
     use super::*;
-    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::tests::{create_test_links, get_dir_entry_for};
     use crate::find::matchers::Matcher;
     use crate::find::tests::FakeDependencies;
 
@@ -616,4 +617,27 @@ mod tests {
             "user-executable pattern should not match file"
         );
     }
+
+    #[test]
+    fn perm_matches_symlink_itself() {
+        // symlinks aren't followed, so we should see the link's own mode
+        // (always 777 on Linux), not that of its target.
+        create_test_links();
+        let link = get_dir_entry_for("test_data/links", "link-f");
+        let deps = FakeDependencies::new();
+
+        if cfg!(target_os = "linux") {
+            let matcher = PermMatcher::new("777").unwrap();
+            assert!(
+                matcher.matches(&link, &mut deps.new_matcher_io()),
+                "symlink should have mode 777"
+            );
+        }
+
+        let matcher = PermMatcher::new("-u+x").unwrap();
+        assert!(
+            matcher.matches(&link, &mut deps.new_matcher_io()),
+            "symlink mode shouldn't come from its non-executable target"
+        );
+    }
 }
@@ -4,6 +4,7 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::io::{stderr, ErrorKind, Write};
 use walkdir::DirEntry;
 
 use super::{Matcher, MatcherIO};
@@ -23,12 +24,25 @@ impl Printer {
 
 impl Matcher for Printer {
     fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
-        writeln!(
-            matcher_io.deps.get_output().borrow_mut(),
-            "{}",
-            file_info.path().to_string_lossy()
-        )
-        .unwrap();
+        let path = matcher_io
+            .quoting_style()
+            .quote(&file_info.path().to_string_lossy());
+        let path = match matcher_io.ls_colors() {
+            Some(ls_colors) => ls_colors.paint(file_info, &path, matcher_io),
+            None => path.to_string(),
+        };
+        let result = writeln!(matcher_io.deps.get_output().borrow_mut(), "{}", path);
+        match result {
+            Ok(_) => {}
+            // Whatever we were writing to has gone away (e.g. `find | head`),
+            // so there's no point carrying on, but it's not an error either.
+            Err(ref e) if e.kind() == ErrorKind::BrokenPipe => matcher_io.mark_quit(),
+            Err(e) => {
+                writeln!(&mut stderr(), "find: write error: {}", e).unwrap();
+                matcher_io.mark_error();
+                matcher_io.mark_quit();
+            }
+        }
         true
     }
 
@@ -38,13 +52,14 @@ impl Matcher for Printer {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
     use crate::find::matchers::tests::get_dir_entry_for;
-    use crate::find::matchers::Matcher;
+    use crate::find::matchers::{LsColors, Matcher, QuotingStyle};
     use crate::find::tests::fix_up_slashes;
     use crate::find::tests::FakeDependencies;
+    use crate::find::Config;
+    use std::rc::Rc;
 
     #[test]
     fn prints() {
@@ -58,4 +73,40 @@ mod tests {
             deps.get_output_as_string()
         );
     }
+
+    #[test]
+    fn prints_in_color() {
+        let dir = get_dir_entry_for("./test_data", "simple");
+
+        let matcher = Printer::new();
+        let deps = FakeDependencies::new();
+        let config = Config {
+            ls_colors: Some(Rc::new(LsColors::new("di=01;34"))),
+            ..Config::default()
+        };
+        let mut matcher_io = MatcherIO::new(&deps, &config);
+        assert!(matcher.matches(&dir, &mut matcher_io));
+        assert_eq!(
+            fix_up_slashes("\x1b[01;34m./test_data/simple\x1b[0m\n"),
+            deps.get_output_as_string()
+        );
+    }
+
+    #[test]
+    fn prints_quoted() {
+        let abbbc = get_dir_entry_for("./test_data/simple", "abbbc");
+
+        let matcher = Printer::new();
+        let deps = FakeDependencies::new();
+        let config = Config {
+            quoting_style: QuotingStyle::C,
+            ..Config::default()
+        };
+        let mut matcher_io = MatcherIO::new(&deps, &config);
+        assert!(matcher.matches(&abbbc, &mut matcher_io));
+        assert_eq!(
+            fix_up_slashes("\"./test_data/simple/abbbc\"\n"),
+            deps.get_output_as_string()
+        );
+    }
 }
@@ -28,7 +28,6 @@ impl Matcher for PruneMatcher {
     }
 }
 #[cfg(test)]
-
 mod tests {
     use super::*;
     use crate::find::matchers::tests::get_dir_entry_for;
@@ -0,0 +1,186 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Quoting of printed file names, using the same styles as coreutils'
+//! `ls --quoting-style`, so that names containing newlines, control
+//! characters or shell metacharacters can be printed unambiguously.
+
+use std::error::Error;
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum QuotingStyle {
+    /// Names are printed exactly as they are.
+    #[default]
+    Literal,
+    /// Names are quoted for the shell, but only if they need it.
+    Shell,
+    /// Names are always quoted for the shell.
+    ShellAlways,
+    /// Names are quoted as C string literals.
+    C,
+    /// Like C, but without the surrounding double quotes.
+    Escape,
+    /// Names are printed as they are, except for control characters, which
+    /// are escaped as for C. This stops names from sending escape sequences
+    /// to a terminal.
+    EscapeControl,
+}
+
+impl FromStr for QuotingStyle {
+    type Err = Box<dyn Error>;
+    fn from_str(s: &str) -> Result<QuotingStyle, Box<dyn Error>> {
+        Ok(match s {
+            "literal" => QuotingStyle::Literal,
+            "shell" => QuotingStyle::Shell,
+            "shell-always" => QuotingStyle::ShellAlways,
+            "c" => QuotingStyle::C,
+            "escape" => QuotingStyle::Escape,
+            "escape-control" => QuotingStyle::EscapeControl,
+            _ => {
+                return Err(From::from(format!(
+                    "invalid quoting style '{}'. Only allowed values are \
+                     literal, shell, shell-always, c, escape or escape-control",
+                    s
+                )))
+            }
+        })
+    }
+}
+
+/// Quotes `name` in single quotes, which the shell leaves entirely alone
+/// except for the closing quote. So any single quotes inside have to be
+/// closed, backslashed and reopened.
+fn shell_quote(name: &str) -> String {
+    format!("'{}'", name.replace('\'', r"'\''"))
+}
+
+fn needs_shell_quoting(name: &str) -> bool {
+    name.is_empty()
+        || name.chars().any(|c| {
+            !(c.is_alphanumeric()
+                || matches!(c, '%' | '+' | ',' | '-' | '.' | '/' | ':' | '=' | '@' | '_'))
+        })
+}
+
+/// Appends the C escape sequence for the control character `c`.
+fn push_escaped_control(escaped: &mut String, c: char) {
+    match c {
+        '\x07' => escaped.push_str(r"\a"),
+        '\x08' => escaped.push_str(r"\b"),
+        '\x0c' => escaped.push_str(r"\f"),
+        '\n' => escaped.push_str(r"\n"),
+        '\r' => escaped.push_str(r"\r"),
+        '\t' => escaped.push_str(r"\t"),
+        '\x0b' => escaped.push_str(r"\v"),
+        c => {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                write!(escaped, "\\{:03o}", byte).unwrap();
+            }
+        }
+    }
+}
+
+/// Escapes `name` as the contents of a C string literal. Spaces are only
+/// escaped when there are no surrounding quotes to show where the name ends.
+fn c_escape(name: &str, escape_spaces: bool) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '"' if !escape_spaces => escaped.push_str("\\\""),
+            ' ' if escape_spaces => escaped.push_str(r"\ "),
+            c if c.is_control() => push_escaped_control(&mut escaped, c),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_control(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_control() {
+            push_escaped_control(&mut escaped, c);
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+impl QuotingStyle {
+    /// The style to use when none was asked for. Like ls, we don't print
+    /// control characters to a terminal as they are, but pass them through
+    /// to anything else untouched.
+    pub fn default_for(is_terminal: bool) -> QuotingStyle {
+        if is_terminal {
+            QuotingStyle::EscapeControl
+        } else {
+            QuotingStyle::Literal
+        }
+    }
+
+    pub fn quote(self, name: &str) -> String {
+        match self {
+            QuotingStyle::Literal => name.to_string(),
+            QuotingStyle::Shell if !needs_shell_quoting(name) => name.to_string(),
+            QuotingStyle::Shell | QuotingStyle::ShellAlways => shell_quote(name),
+            QuotingStyle::C => format!("\"{}\"", c_escape(name, false)),
+            QuotingStyle::Escape => c_escape(name, true),
+            QuotingStyle::EscapeControl => escape_control(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_in_each_style() {
+        let name = "it's a\nname\t\x01";
+        for (style, expected) in &[
+            ("literal", "it's a\nname\t\x01"),
+            ("shell", "'it'\\''s a\nname\t\x01'"),
+            ("shell-always", "'it'\\''s a\nname\t\x01'"),
+            ("c", "\"it's a\\nname\\t\\001\""),
+            ("escape", "it's\\ a\\nname\\t\\001"),
+            ("escape-control", "it's a\\nname\\t\\001"),
+        ] {
+            let style: QuotingStyle = style.parse().unwrap();
+            assert_eq!(style.quote(name), *expected, "for {:?}", style);
+        }
+    }
+
+    #[test]
+    fn shell_only_quotes_when_needed() {
+        assert_eq!(
+            QuotingStyle::Shell.quote("./dir/file-1.txt"),
+            "./dir/file-1.txt"
+        );
+        assert_eq!(QuotingStyle::Shell.quote("a*"), "'a*'");
+        assert_eq!(QuotingStyle::Shell.quote(""), "''");
+        assert_eq!(QuotingStyle::ShellAlways.quote("plain"), "'plain'");
+    }
+
+    #[test]
+    fn default_depends_on_terminal() {
+        let name = "evil\x1b[2Jname";
+        assert_eq!(
+            QuotingStyle::default_for(true).quote(name),
+            "evil\\033[2Jname"
+        );
+        assert_eq!(QuotingStyle::default_for(false).quote(name), name);
+    }
+
+    #[test]
+    fn rejects_unknown_style() {
+        assert!("locale".parse::<QuotingStyle>().is_err());
+    }
+}
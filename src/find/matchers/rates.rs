@@ -0,0 +1,100 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::cell::Cell;
+use std::io::{stderr, Write};
+use std::path::PathBuf;
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+/// Wraps another matcher, counting how often it's evaluated and how often it
+/// matches, for -D rates. The counts are written to stderr when find has
+/// finished.
+pub struct RatesMatcher {
+    predicate: String,
+    submatcher: Box<dyn Matcher>,
+    evaluations: Cell<u64>,
+    successes: Cell<u64>,
+}
+
+impl RatesMatcher {
+    pub fn new(predicate: &str, submatcher: Box<dyn Matcher>) -> RatesMatcher {
+        RatesMatcher {
+            predicate: predicate.to_string(),
+            submatcher,
+            evaluations: Cell::new(0),
+            successes: Cell::new(0),
+        }
+    }
+
+    pub fn new_box(predicate: &str, submatcher: Box<dyn Matcher>) -> Box<dyn Matcher> {
+        Box::new(RatesMatcher::new(predicate, submatcher))
+    }
+
+    fn summary(&self) -> String {
+        let evaluations = self.evaluations.get();
+        let successes = self.successes.get();
+        let rate = if evaluations == 0 {
+            0.0
+        } else {
+            successes as f64 / evaluations as f64
+        };
+        format!(
+            "{}: {}/{}={:.2}",
+            self.predicate, successes, evaluations, rate
+        )
+    }
+}
+
+impl Matcher for RatesMatcher {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        self.evaluations.set(self.evaluations.get() + 1);
+        let result = self.submatcher.matches(file_info, matcher_io);
+        if result {
+            self.successes.set(self.successes.get() + 1);
+        }
+        result
+    }
+
+    fn has_side_effects(&self) -> bool {
+        self.submatcher.has_side_effects()
+    }
+
+    fn finished_dir(&self, dir: &PathBuf) {
+        self.submatcher.finished_dir(dir);
+    }
+
+    fn finished(&self, matcher_io: &mut MatcherIO) {
+        writeln!(&mut stderr(), "{}", self.summary()).unwrap();
+        self.submatcher.finished(matcher_io);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find::matchers::logical_matchers::{FalseMatcher, TrueMatcher};
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::Matcher;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn counts_evaluations_and_successes() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+
+        let matcher = RatesMatcher::new("-true", TrueMatcher::new_box());
+        assert_eq!(matcher.summary(), "-true: 0/0=0.00");
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        assert_eq!(matcher.summary(), "-true: 2/2=1.00");
+
+        let matcher = RatesMatcher::new("-false", FalseMatcher::new_box());
+        assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        assert_eq!(matcher.summary(), "-false: 0/1=0.00");
+    }
+}
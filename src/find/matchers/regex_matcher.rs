@@ -0,0 +1,488 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Implements -regex and -iregex. The regex crate only understands one
+//! (POSIX extended-like) syntax, so patterns in the other flavours that
+//! -regextype can select are translated into it first.
+
+use regex::{Regex, RegexBuilder};
+use std::error::Error;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+/// The regular expression flavours that -regextype accepts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RegexType {
+    /// GNU find's default: grouping and alternation need a backslash (\( \)
+    /// and \|), but + and ? are special without one.
+    #[default]
+    Emacs,
+    /// Every operator other than * needs a backslash to be special.
+    PosixBasic,
+    /// No operators need a backslash.
+    PosixExtended,
+}
+
+impl FromStr for RegexType {
+    type Err = Box<dyn Error>;
+    fn from_str(s: &str) -> Result<RegexType, Box<dyn Error>> {
+        Ok(match s {
+            "default" | "emacs" | "findutils-default" => RegexType::Emacs,
+            "posix-basic" | "sed" | "grep" => RegexType::PosixBasic,
+            "posix-extended" | "egrep" | "posix-egrep" | "awk" | "posix-awk" => {
+                RegexType::PosixExtended
+            }
+            _ => {
+                return Err(From::from(format!(
+                    "Unknown regular expression type '{}'; valid types are \
+                     default, findutils-default, emacs, posix-basic, sed, grep, \
+                     posix-extended, egrep, posix-egrep, awk and posix-awk",
+                    s
+                )))
+            }
+        })
+    }
+}
+
+/// How much of the path a -regex has to match, as set by the non-standard
+/// -regex-anchor.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RegexAnchor {
+    /// Like GNU find, the whole path.
+    #[default]
+    Full,
+    /// Just the start of the path.
+    Prefix,
+    /// Anywhere in the path, as grep would.
+    Substring,
+}
+
+impl FromStr for RegexAnchor {
+    type Err = Box<dyn Error>;
+    fn from_str(s: &str) -> Result<RegexAnchor, Box<dyn Error>> {
+        Ok(match s {
+            "full" => RegexAnchor::Full,
+            "prefix" => RegexAnchor::Prefix,
+            "substring" => RegexAnchor::Substring,
+            _ => {
+                return Err(From::from(format!(
+                    "Unknown -regex-anchor '{}'; valid values are full, prefix \
+                     and substring",
+                    s
+                )))
+            }
+        })
+    }
+}
+
+impl RegexAnchor {
+    /// What goes in front of the translated pattern.
+    fn prefix(self) -> &'static str {
+        match self {
+            RegexAnchor::Full | RegexAnchor::Prefix => "^(?:",
+            RegexAnchor::Substring => "(?:",
+        }
+    }
+
+    /// What goes after the translated pattern.
+    fn suffix(self) -> &'static str {
+        match self {
+            RegexAnchor::Full => ")$",
+            RegexAnchor::Prefix | RegexAnchor::Substring => ")",
+        }
+    }
+
+    fn wrap(self, translated: &str) -> String {
+        format!("{}{}{}", self.prefix(), translated, self.suffix())
+    }
+}
+
+impl RegexType {
+    /// Whether `c` is an operator without a backslash (and so a literal
+    /// with one).
+    fn is_special_unescaped(self, c: char) -> bool {
+        match self {
+            RegexType::Emacs => matches!(c, '+' | '?'),
+            RegexType::PosixBasic => false,
+            RegexType::PosixExtended => true,
+        }
+    }
+
+    /// Translates `pattern` into the syntax the regex crate expects. The
+    /// regex crate's own [:alpha:] etc. are ASCII-only, so if `unicode` is
+    /// set they're replaced with their Unicode equivalents.
+    fn translate(self, pattern: &str, unicode: bool) -> String {
+        let mut translated = String::with_capacity(pattern.len());
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '[' => self.translate_bracket(&mut chars, &mut translated, unicode),
+                '\\' => match chars.next() {
+                    Some(c) if is_operator(c) && !self.is_special_unescaped(c) => {
+                        translated.push(c)
+                    }
+                    Some(c) => {
+                        translated.push('\\');
+                        translated.push(c);
+                    }
+                    None => translated.push('\\'),
+                },
+                c if is_operator(c) && !self.is_special_unescaped(c) => {
+                    translated.push('\\');
+                    translated.push(c);
+                }
+                c => translated.push(c),
+            }
+        }
+        translated
+    }
+
+    /// Translates a bracket expression, whose opening '[' has already been
+    /// consumed. For posix-extended these are copied verbatim, as the regex
+    /// crate's syntax is close enough. The other flavours follow POSIX, where
+    /// a backslash isn't special in a bracket expression and a ']' straight
+    /// after the '[' or '[^' is a literal rather than the end of it.
+    fn translate_bracket(
+        self,
+        chars: &mut Peekable<Chars>,
+        translated: &mut String,
+        unicode: bool,
+    ) {
+        let verbatim = self == RegexType::PosixExtended;
+        translated.push('[');
+        if chars.peek() == Some(&'^') {
+            translated.push(chars.next().unwrap());
+        }
+        if chars.peek() == Some(&']') {
+            chars.next();
+            translated.push_str(if verbatim { "]" } else { r"\]" });
+        }
+        while let Some(c) = chars.next() {
+            match c {
+                ']' => break,
+                '[' if chars.peek() == Some(&':') => {
+                    let rest: String = chars.clone().skip(1).collect();
+                    match rest.find(":]") {
+                        Some(end) => {
+                            let name = &rest[..end];
+                            translated.push_str(&posix_class(name, unicode));
+                            // skip the name and the colons and ']' around it
+                            for _ in 0..name.chars().count() + 3 {
+                                chars.next();
+                            }
+                        }
+                        None if verbatim => translated.push('['),
+                        None => translated.push_str(r"\["),
+                    }
+                }
+                '\\' if verbatim => {
+                    translated.push('\\');
+                    if let Some(c) = chars.next() {
+                        translated.push(c);
+                    }
+                }
+                '[' | '\\' if !verbatim => {
+                    translated.push('\\');
+                    translated.push(c);
+                }
+                c => translated.push(c),
+            }
+        }
+        translated.push(']');
+    }
+}
+
+/// Translates the POSIX character class [:name:] for use inside a bracket
+/// expression. Unknown names are passed through for the regex crate to
+/// complain about.
+fn posix_class(name: &str, unicode: bool) -> String {
+    if unicode {
+        match name {
+            "alpha" => return r"\p{Alphabetic}".to_string(),
+            "alnum" => return r"\p{Alphabetic}\p{Nd}".to_string(),
+            "upper" => return r"\p{Uppercase}".to_string(),
+            "lower" => return r"\p{Lowercase}".to_string(),
+            "space" => return r"\p{White_Space}".to_string(),
+            _ => {}
+        }
+    }
+    format!("[:{}:]", name)
+}
+
+/// The characters whose meaning depends on the regex flavour.
+fn is_operator(c: char) -> bool {
+    matches!(c, '(' | ')' | '|' | '{' | '}' | '+' | '?')
+}
+
+/// This matcher checks whether the whole path (not just the name) matches a
+/// regular expression (or, with -regex-anchor, whether its start or any part
+/// of it does).
+pub struct RegexMatcher {
+    regex: Regex,
+    /// Used instead of `regex` for paths that aren't valid UTF-8, so that
+    /// they're matched byte by byte (where "." is any byte, and classes like
+    /// [:alpha:] are ASCII-only) rather than after a lossy conversion. It
+    /// can't be built if the pattern itself contains non-ASCII characters,
+    /// in which case we fall back to the lossy conversion.
+    #[cfg(unix)]
+    bytes_regex: Option<regex::bytes::Regex>,
+}
+
+impl RegexMatcher {
+    #[cfg(test)]
+    pub fn new(
+        regex_type: RegexType,
+        pattern: &str,
+        ignore_case: bool,
+    ) -> Result<RegexMatcher, Box<dyn Error>> {
+        // like GNU find, by default the pattern has to match the whole path
+        RegexMatcher::new_anchored(regex_type, RegexAnchor::Full, pattern, ignore_case)
+    }
+
+    pub fn new_anchored(
+        regex_type: RegexType,
+        anchor: RegexAnchor,
+        pattern: &str,
+        ignore_case: bool,
+    ) -> Result<RegexMatcher, Box<dyn Error>> {
+        let regex = RegexBuilder::new(&anchor.wrap(&regex_type.translate(pattern, true)))
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| format!("invalid regular expression '{}': {}", pattern, e))?;
+        #[cfg(unix)]
+        let bytes_regex =
+            regex::bytes::RegexBuilder::new(&anchor.wrap(&regex_type.translate(pattern, false)))
+                .case_insensitive(ignore_case)
+                .unicode(false)
+                .build()
+                .ok();
+        Ok(RegexMatcher {
+            regex,
+            #[cfg(unix)]
+            bytes_regex,
+        })
+    }
+
+    pub fn new_box(
+        regex_type: RegexType,
+        anchor: RegexAnchor,
+        pattern: &str,
+        ignore_case: bool,
+    ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(RegexMatcher::new_anchored(
+            regex_type,
+            anchor,
+            pattern,
+            ignore_case,
+        )?))
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+        let path = file_info.path();
+        if let Some(path) = path.to_str() {
+            return self.regex.is_match(path);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            if let Some(ref bytes_regex) = self.bytes_regex {
+                return bytes_regex.is_match(path.as_os_str().as_bytes());
+            }
+        }
+        self.regex.is_match(path.to_string_lossy().as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn translates_each_flavour() {
+        let pattern = r"a\(b\|c\)+\{2\}(d|e)?[]\(]";
+        assert_eq!(
+            RegexType::Emacs.translate(pattern, true),
+            r"a(b|c)+{2}\(d\|e\)?[\]\\(]"
+        );
+        assert_eq!(
+            RegexType::PosixBasic.translate(pattern, true),
+            r"a(b|c)\+{2}\(d\|e\)\?[\]\\(]"
+        );
+        assert_eq!(RegexType::PosixExtended.translate(pattern, true), pattern);
+    }
+
+    #[test]
+    fn translates_character_classes() {
+        for regex_type in &[
+            RegexType::Emacs,
+            RegexType::PosixBasic,
+            RegexType::PosixExtended,
+        ] {
+            assert_eq!(
+                regex_type.translate("[^[:alpha:]_]", true),
+                r"[^\p{Alphabetic}_]"
+            );
+            assert_eq!(
+                regex_type.translate("[^[:alpha:]_]", false),
+                "[^[:alpha:]_]"
+            );
+            assert_eq!(regex_type.translate("[[:digit:]]", true), "[[:digit:]]");
+        }
+    }
+
+    #[test]
+    fn regex_alpha_matches_unicode_letters() {
+        let temp_dir = TempDir::new("regex_alpha_matches_unicode_letters").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        File::create(temp_dir.path().join("caf\u{e9}")).expect("create temp file");
+        let file = get_dir_entry_for(&temp_dir_path, "caf\u{e9}");
+        let deps = FakeDependencies::new();
+
+        for regex_type in &["emacs", "posix-basic", "posix-extended"] {
+            let matcher =
+                RegexMatcher::new(regex_type.parse().unwrap(), ".*/caf[[:alpha:]]", false).unwrap();
+            assert!(
+                matcher.matches(&file, &mut deps.new_matcher_io()),
+                "[[:alpha:]] should match a non-ASCII letter with -regextype {}",
+                regex_type
+            );
+        }
+    }
+
+    // macOS insists on file names being valid UTF-8
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn regex_matches_non_utf8_path_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new("regex_matches_non_utf8_path_bytes").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        let name = OsStr::from_bytes(b"caf\xe9");
+        File::create(temp_dir.path().join(name)).expect("create temp file");
+        let file = get_dir_entry_for(&temp_dir_path, "caf\u{fffd}");
+        let deps = FakeDependencies::new();
+
+        for (pattern, should_match) in &[
+            (".*/caf.", true),
+            (".*/caf[^a]", true),
+            // in byte mode classes are ASCII-only, and 0xe9 isn't a letter
+            // of any kind on its own
+            (".*/caf[[:alpha:]]", false),
+        ] {
+            let matcher = RegexMatcher::new(RegexType::Emacs, pattern, false).unwrap();
+            assert_eq!(
+                matcher.matches(&file, &mut deps.new_matcher_io()),
+                *should_match,
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn regex_matches_whole_path() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+
+        let matcher = RegexMatcher::new(RegexType::Emacs, ".*/ab+c", false).unwrap();
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        let matcher = RegexMatcher::new(RegexType::Emacs, "ab+c", false).unwrap();
+        assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        let matcher = RegexMatcher::new(RegexType::Emacs, ".*/AB+C", false).unwrap();
+        assert!(!matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+        let matcher = RegexMatcher::new(RegexType::Emacs, ".*/AB+C", true).unwrap();
+        assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+    }
+
+    #[test]
+    fn regex_types_differ() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+
+        for (regex_type, pattern, should_match) in &[
+            ("emacs", r".*/\(x\|a\)b+c", true),
+            ("sed", r".*/\(x\|a\)b+c", false),
+            ("sed", r".*/\(x\|a\)b\+c", true),
+            ("posix-extended", r".*/(x|a)b+c", true),
+            ("posix-extended", r".*/\(x\|a\)b+c", false),
+        ] {
+            let matcher = RegexMatcher::new(regex_type.parse().unwrap(), pattern, false).unwrap();
+            assert_eq!(
+                matcher.matches(&abbbc, &mut deps.new_matcher_io()),
+                *should_match,
+                "{} with -regextype {}",
+                pattern,
+                regex_type
+            );
+        }
+    }
+
+    #[test]
+    fn anchors() {
+        let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+        let matches = |anchor, pattern| {
+            RegexMatcher::new_anchored(RegexType::Emacs, anchor, pattern, false)
+                .unwrap()
+                .matches(&abbbc, &mut deps.new_matcher_io())
+        };
+
+        // the path is test_data/simple/abbbc
+        assert!(matches(RegexAnchor::Full, "test_data/simple/ab+c"));
+        assert!(!matches(RegexAnchor::Full, "test_data/simple"));
+        assert!(!matches(RegexAnchor::Full, "simple/ab+c"));
+
+        assert!(matches(RegexAnchor::Prefix, "test_data/simple/ab+c"));
+        assert!(matches(RegexAnchor::Prefix, "test_data/simple"));
+        assert!(!matches(RegexAnchor::Prefix, "simple/ab+c"));
+
+        assert!(matches(RegexAnchor::Substring, "test_data/simple/ab+c"));
+        assert!(matches(RegexAnchor::Substring, "test_data/simple"));
+        assert!(matches(RegexAnchor::Substring, "simple/ab+c"));
+        assert!(!matches(RegexAnchor::Substring, "simple/c"));
+    }
+
+    #[test]
+    fn bad_regex_anchor() {
+        assert_eq!(
+            "prefix".parse::<RegexAnchor>().unwrap(),
+            RegexAnchor::Prefix
+        );
+        assert!("suffix".parse::<RegexAnchor>().is_err());
+    }
+
+    #[test]
+    fn bad_pattern_error_names_pattern() {
+        for &(regex_type, pattern) in &[
+            (RegexType::PosixExtended, "ab{2,1}"),
+            (RegexType::Emacs, r"ab\)c"),
+        ] {
+            match RegexMatcher::new(regex_type, pattern, false) {
+                Err(e) => assert!(e
+                    .to_string()
+                    .starts_with(&format!("invalid regular expression '{}': ", pattern))),
+                Ok(_) => panic!("{} should fail", pattern),
+            }
+        }
+    }
+
+    #[test]
+    fn bad_regex_type_and_pattern() {
+        assert!("perl".parse::<RegexType>().is_err());
+        assert!(RegexMatcher::new(RegexType::PosixExtended, "(", false).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2017 Google Inc.
+//
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Implements -samefile. Two names refer to the same file if they have the
+//! same device and inode numbers. As we never follow symlinks (GNU's -P), a
+//! reference that is itself a symlink means the link, not what it points to.
+
+use std::error::Error;
+use std::fs::Metadata;
+use std::io::{self, stderr, Write};
+use walkdir::DirEntry;
+
+use super::{Matcher, MatcherIO};
+
+pub struct SameFileMatcher {
+    #[cfg(unix)]
+    dev: u64,
+    #[cfg(unix)]
+    ino: u64,
+}
+
+impl SameFileMatcher {
+    /// As with -newer, the reference is stat'd once, here, with `stat`.
+    #[cfg(unix)]
+    pub fn new_with_stat(
+        path_to_file: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<SameFileMatcher, Box<dyn Error>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = stat(path_to_file)?;
+        Ok(SameFileMatcher {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new_with_stat(
+        _path_to_file: &str,
+        _stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<SameFileMatcher, Box<dyn Error>> {
+        Err(From::from("-samefile is not available on this platform"))
+    }
+
+    #[cfg(test)]
+    pub fn new(path_to_file: &str) -> Result<SameFileMatcher, Box<dyn Error>> {
+        SameFileMatcher::new_with_stat(path_to_file, |path| std::fs::symlink_metadata(path))
+    }
+
+    pub fn new_box_with_stat(
+        path_to_file: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(SameFileMatcher::new_with_stat(
+            path_to_file,
+            stat,
+        )?))
+    }
+}
+
+impl Matcher for SameFileMatcher {
+    #[cfg(unix)]
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        match matcher_io.metadata(file_info) {
+            Ok(metadata) => metadata.dev() == self.dev && metadata.ino() == self.ino,
+            Err(e) => {
+                writeln!(
+                    &mut stderr(),
+                    "Error getting metadata for {}: {}",
+                    file_info.path().to_string_lossy(),
+                    e
+                )
+                .unwrap();
+                matcher_io.mark_error();
+                false
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn matches(&self, _dummy_file_info: &DirEntry, _: &mut MatcherIO) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use crate::find::matchers::tests::{create_test_links, get_dir_entry_for};
+    use crate::find::tests::FakeDependencies;
+
+    #[test]
+    fn samefile_matches_itself() {
+        let file = get_dir_entry_for("test_data/links", "abbbc");
+        let other = get_dir_entry_for("test_data/links", "subdir");
+        let matcher = SameFileMatcher::new("test_data/links/abbbc").unwrap();
+        let deps = FakeDependencies::new();
+        assert!(matcher.matches(&file, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&other, &mut deps.new_matcher_io()));
+    }
+
+    #[test]
+    fn samefile_symlink_reference_is_the_link() {
+        // without -L, a symlink reference means the link itself
+        create_test_links();
+        let link = get_dir_entry_for("test_data/links", "link-f");
+        let target = get_dir_entry_for("test_data/links", "abbbc");
+        let matcher = SameFileMatcher::new("test_data/links/link-f").unwrap();
+        let deps = FakeDependencies::new();
+        assert!(matcher.matches(&link, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&target, &mut deps.new_matcher_io()));
+    }
+
+    #[test]
+    fn samefile_missing_reference() {
+        assert!(SameFileMatcher::new("test_data/links/does_not_exist").is_err());
+    }
+}
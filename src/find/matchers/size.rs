@@ -5,11 +5,12 @@
 // https://opensource.org/licenses/MIT.
 
 use std::error::Error;
-use std::io::{stderr, Write};
+use std::fs::Metadata;
+use std::io::{self, stderr, Write};
 use std::str::FromStr;
 use walkdir::DirEntry;
 
-use super::{ComparableValue, Matcher, MatcherIO};
+use super::{convert_arg_to_comparable_value_and_suffix, ComparableValue, Matcher, MatcherIO};
 
 #[derive(Clone, Copy, Debug)]
 enum Unit {
@@ -33,7 +34,7 @@ impl FromStr for Unit {
             "G" => Unit::GibiByte,
             _ => {
                 return Err(From::from(format!(
-                    "Invalid suffix {} for -size. Only allowed \
+                    "invalid -size type '{}'. Only allowed \
                      values are <nothing>, b, c, w, k, M or G",
                     s
                 )));
@@ -64,11 +65,57 @@ fn byte_size_to_unit_size(unit: Unit, byte_size: u64) -> u64 {
     ((byte_size - 1) >> bits_to_shift) + 1
 }
 
+enum SizeBound {
+    Compare(ComparableValue, Unit),
+    /// The non-standard N1-N2 form. Both ends are inclusive, and each is
+    /// rounded in its own unit, so 1k-10c is as empty as it looks.
+    Range((u64, Unit), (u64, Unit)),
+}
+
+impl SizeBound {
+    fn parse(value_to_match: ComparableValue, suffix_string: &str) -> Result<Self, Box<dyn Error>> {
+        let (low, dash) = match (value_to_match, suffix_string.find('-')) {
+            (ComparableValue::EqualTo(low), Some(dash)) => (low, dash),
+            (value_to_match, _) => {
+                return Ok(SizeBound::Compare(value_to_match, suffix_string.parse()?));
+            }
+        };
+        let (low_suffix, high_string) = suffix_string.split_at(dash);
+        let high_string = &high_string[1..];
+        match convert_arg_to_comparable_value_and_suffix("-size", high_string)? {
+            (ComparableValue::EqualTo(high), high_suffix) => Ok(SizeBound::Range(
+                (low, low_suffix.parse()?),
+                (high, high_suffix.parse()?),
+            )),
+            _ => Err(From::from(format!(
+                "invalid -size range end `{}'. Expected an unsigned integer",
+                high_string
+            ))),
+        }
+    }
+
+    fn matches(&self, byte_size: u64) -> bool {
+        match *self {
+            SizeBound::Compare(ref value_to_match, unit) => {
+                value_to_match.matches(byte_size_to_unit_size(unit, byte_size))
+            }
+            SizeBound::Range((low, low_unit), (high, high_unit)) => {
+                byte_size_to_unit_size(low_unit, byte_size) >= low
+                    && byte_size_to_unit_size(high_unit, byte_size) <= high
+            }
+        }
+    }
+}
+
 /// Matcher that checks whether a file's size if {less than | equal to | more than}
 /// N units in size.
 pub struct SizeMatcher {
-    value_to_match: ComparableValue,
-    unit: Unit,
+    bound: SizeBound,
+    /// Whether to use the space actually allocated on disk (for the
+    /// non-standard -size-disk) rather than the apparent size. The two
+    /// differ for sparse files, and for small files that still take up a
+    /// whole block.
+    disk_usage: bool,
 }
 
 impl SizeMatcher {
@@ -77,25 +124,82 @@ impl SizeMatcher {
         suffix_string: &str,
     ) -> Result<SizeMatcher, Box<dyn Error>> {
         Ok(SizeMatcher {
-            unit: suffix_string.parse()?,
-            value_to_match,
+            bound: SizeBound::parse(value_to_match, suffix_string)?,
+            disk_usage: false,
         })
     }
 
+    pub fn new_disk_usage(
+        value_to_match: ComparableValue,
+        suffix_string: &str,
+    ) -> Result<SizeMatcher, Box<dyn Error>> {
+        Ok(SizeMatcher {
+            disk_usage: true,
+            ..SizeMatcher::new(value_to_match, suffix_string)?
+        })
+    }
+
+    /// For the non-standard -sizelike, which matches files of exactly the
+    /// same (apparent) size as the reference file. As with -newer, the
+    /// reference is stat'd once, here, with `stat`.
+    pub fn new_like_with_stat(
+        path_to_file: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<SizeMatcher, Box<dyn Error>> {
+        let size = stat(path_to_file)?.len();
+        SizeMatcher::new(ComparableValue::EqualTo(size), "c")
+    }
+
+    #[cfg(test)]
+    pub fn new_like(path_to_file: &str) -> Result<SizeMatcher, Box<dyn Error>> {
+        SizeMatcher::new_like_with_stat(path_to_file, |path| std::fs::symlink_metadata(path))
+    }
+
     pub fn new_box(
         value_to_match: ComparableValue,
         suffix_string: &str,
     ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
         Ok(Box::new(SizeMatcher::new(value_to_match, suffix_string)?))
     }
+
+    pub fn new_like_box_with_stat(
+        path_to_file: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(SizeMatcher::new_like_with_stat(
+            path_to_file,
+            stat,
+        )?))
+    }
+
+    pub fn new_disk_usage_box(
+        value_to_match: ComparableValue,
+        suffix_string: &str,
+    ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(SizeMatcher::new_disk_usage(
+            value_to_match,
+            suffix_string,
+        )?))
+    }
+
+    fn size_of(&self, metadata: &Metadata) -> u64 {
+        #[cfg(unix)]
+        {
+            if self.disk_usage {
+                use std::os::unix::fs::MetadataExt;
+                // st_blocks is always in 512 byte units
+                return metadata.blocks() * 512;
+            }
+        }
+        // elsewhere there's no portable way to get the disk usage
+        metadata.len()
+    }
 }
 
 impl Matcher for SizeMatcher {
-    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
-        match file_info.metadata() {
-            Ok(metadata) => self
-                .value_to_match
-                .matches(byte_size_to_unit_size(self.unit, metadata.len())),
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        match matcher_io.metadata(file_info) {
+            Ok(metadata) => self.bound.matches(self.size_of(&metadata)),
             Err(e) => {
                 writeln!(
                     &mut stderr(),
@@ -104,6 +208,7 @@ impl Matcher for SizeMatcher {
                     e
                 )
                 .unwrap();
+                matcher_io.mark_error();
                 false
             }
         }
@@ -112,6 +217,8 @@ impl Matcher for SizeMatcher {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use super::*;
     use crate::find::matchers::tests::get_dir_entry_for;
     use crate::find::matchers::{ComparableValue, Matcher};
@@ -155,14 +262,13 @@ mod tests {
 
     #[test]
     fn size_matcher_bad_unit() {
-        if let Err(e) = SizeMatcher::new(ComparableValue::EqualTo(2), "xyz") {
-            assert!(
-                e.to_string().contains("Invalid suffix") && e.to_string().contains("xyz"),
-                "bad description: {}",
-                e
-            );
-        } else {
-            panic!("parsing a unit string should fail");
+        match SizeMatcher::new(ComparableValue::EqualTo(2), "xyz") {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "invalid -size type 'xyz'. Only allowed values are <nothing>, \
+                 b, c, w, k, M or G"
+            ),
+            Ok(_) => panic!("parsing a unit string should fail"),
         }
     }
 
@@ -183,4 +289,106 @@ mod tests {
             "512-byte file should match size of 1 block"
         );
     }
+
+    #[test]
+    fn size_matcher_zero() {
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("size_matcher_zero").unwrap();
+        fs::write(temp_dir.path().join("1byte"), "x").unwrap();
+        let one_byte = get_dir_entry_for(&temp_dir.path().to_string_lossy(), "1byte");
+        let empty = get_dir_entry_for("./test_data/simple", "abbbc");
+        let deps = FakeDependencies::new();
+
+        // as sizes are rounded up, 0 of any unit means exactly 0 bytes
+        for unit in &["", "b", "c", "w", "k", "M", "G"] {
+            let matcher = SizeMatcher::new(ComparableValue::EqualTo(0), unit).unwrap();
+            assert!(
+                matcher.matches(&empty, &mut deps.new_matcher_io()),
+                "empty file should match -size 0{}",
+                unit
+            );
+            assert!(
+                !matcher.matches(&one_byte, &mut deps.new_matcher_io()),
+                "1-byte file should not match -size 0{}",
+                unit
+            );
+        }
+    }
+
+    #[test]
+    fn size_range_matcher() {
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("size_range_matcher").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        fs::write(temp_dir.path().join("5k"), vec![0; 5 << 10]).unwrap();
+        fs::write(temp_dir.path().join("20k"), vec![0; 20 << 10]).unwrap();
+        let five = get_dir_entry_for(&temp_dir_path, "5k");
+        let twenty = get_dir_entry_for(&temp_dir_path, "20k");
+        let deps = FakeDependencies::new();
+
+        let matcher = SizeMatcher::new(ComparableValue::EqualTo(1), "k-10k").unwrap();
+        assert!(matcher.matches(&five, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&twenty, &mut deps.new_matcher_io()));
+
+        // the ends are inclusive, and can have different units
+        let matcher = SizeMatcher::new(ComparableValue::EqualTo(5), "k-20480c").unwrap();
+        assert!(matcher.matches(&five, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&twenty, &mut deps.new_matcher_io()));
+        let matcher = SizeMatcher::new(ComparableValue::EqualTo(6), "k-1M").unwrap();
+        assert!(!matcher.matches(&five, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&twenty, &mut deps.new_matcher_io()));
+
+        assert!(SizeMatcher::new(ComparableValue::EqualTo(1), "k-+10k").is_err());
+        assert!(SizeMatcher::new(ComparableValue::EqualTo(1), "k-10x").is_err());
+        assert!(SizeMatcher::new(ComparableValue::EqualTo(1), "x-10k").is_err());
+    }
+
+    #[test]
+    fn size_like_matcher() {
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("size_like_matcher").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        fs::write(temp_dir.path().join("reference"), "abc").unwrap();
+        fs::write(temp_dir.path().join("same"), "xyz").unwrap();
+        fs::write(temp_dir.path().join("different"), "wxyz").unwrap();
+        let same = get_dir_entry_for(&temp_dir_path, "same");
+        let different = get_dir_entry_for(&temp_dir_path, "different");
+        let deps = FakeDependencies::new();
+
+        let matcher =
+            SizeMatcher::new_like(&temp_dir.path().join("reference").to_string_lossy()).unwrap();
+        assert!(matcher.matches(&same, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&different, &mut deps.new_matcher_io()));
+
+        assert!(SizeMatcher::new_like(&temp_dir.path().join("missing").to_string_lossy()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn size_disk_matcher_sparse_file() {
+        use std::fs::File;
+        use std::os::unix::fs::MetadataExt;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("size_disk_matcher_sparse_file").unwrap();
+        File::create(temp_dir.path().join("sparse"))
+            .and_then(|f| f.set_len(100 << 20))
+            .expect("create sparse file");
+        let file = get_dir_entry_for(&temp_dir.path().to_string_lossy(), "sparse");
+        if file.metadata().unwrap().blocks() * 512 >= 1 << 20 {
+            // the file system doesn't do sparse files
+            return;
+        }
+        let deps = FakeDependencies::new();
+
+        let apparent = SizeMatcher::new(ComparableValue::MoreThan(99), "M").unwrap();
+        assert!(apparent.matches(&file, &mut deps.new_matcher_io()));
+        let disk = SizeMatcher::new_disk_usage(ComparableValue::LessThan(1), "M").unwrap();
+        assert!(disk.matches(&file, &mut deps.new_matcher_io()));
+        let disk = SizeMatcher::new_disk_usage(ComparableValue::MoreThan(99), "M").unwrap();
+        assert!(!disk.matches(&file, &mut deps.new_matcher_io()));
+    }
 }
@@ -4,59 +4,150 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use regex::Regex;
 use std::error::Error;
-use std::fs::{self, Metadata};
-use std::io::{stderr, Write};
-use std::time::SystemTime;
+use std::fs::Metadata;
+use std::io::{self, stderr, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use walkdir::DirEntry;
 
 use super::{ComparableValue, Matcher, MatcherIO};
 
 const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
 
-/// This matcher checks whether a file is newer than the file the matcher is initialized with.
+/// This matcher checks whether one of a file's times (its modification time
+/// unless otherwise specified) is newer than a reference time, taken either
+/// from another file or from a literal date.
 pub struct NewerMatcher {
-    given_modification_time: SystemTime,
+    /// None means "now", i.e. the time find was started.
+    given_time: Option<SystemTime>,
+    file_time_type: FileTimeType,
 }
 
 impl NewerMatcher {
+    /// The reference file is stat'd exactly once, here, while the
+    /// expression is being parsed. That way a relative path is always
+    /// resolved against the directory find was started in, regardless of
+    /// what happens to the working directory during the traversal. `stat`
+    /// is how it gets stat'd, so that several tests with the same reference
+    /// can share the result.
+    pub fn new_with_stat(
+        path_to_file: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<NewerMatcher, Box<dyn Error>> {
+        let metadata = stat(path_to_file)?;
+        Ok(NewerMatcher::new_with_time(
+            FileTimeType::Modified,
+            metadata.modified()?,
+        ))
+    }
+
+    #[cfg(test)]
     pub fn new(path_to_file: &str) -> Result<NewerMatcher, Box<dyn Error>> {
-        let metadata = fs::metadata(path_to_file)?;
-        Ok(NewerMatcher {
-            given_modification_time: metadata.modified()?,
-        })
+        NewerMatcher::new_with_stat(path_to_file, |path| std::fs::metadata(path))
+    }
+
+    pub fn new_with_time(file_time_type: FileTimeType, given_time: SystemTime) -> NewerMatcher {
+        NewerMatcher {
+            given_time: Some(given_time),
+            file_time_type,
+        }
+    }
+
+    /// Builds a matcher for files whose time is newer than the start of this
+    /// run of find. That's taken from MatcherIO::now, which doesn't change
+    /// over the course of a run, so every file is compared against the same
+    /// moment however long the search takes.
+    pub fn new_since_start(file_time_type: FileTimeType) -> NewerMatcher {
+        NewerMatcher {
+            given_time: None,
+            file_time_type,
+        }
     }
 
-    pub fn new_box(path_to_file: &str) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
-        Ok(Box::new(NewerMatcher::new(path_to_file)?))
+    pub fn new_box_with_stat(
+        path_to_file: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(NewerMatcher::new_with_stat(path_to_file, stat)?))
+    }
+
+    #[cfg(test)]
+    pub fn new_box_xy(option: &str, reference: &str) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        NewerMatcher::new_box_xy_with_stat(option, reference, |path| std::fs::metadata(path))
+    }
+
+    /// Builds the matcher for -newerXY (e.g. -newermt). X is the time of the
+    /// file being tested and Y the time of the reference, each being one of
+    /// a (access), B (birth), c (inode change) or m (modification). Y can
+    /// also be t, in which case the reference is a literal date (or "now",
+    /// meaning the time find was started) rather than a file name. t makes
+    /// no sense as X, so isn't allowed there. A reference file is stat'd
+    /// with `stat`, as for new_with_stat.
+    pub fn new_box_xy_with_stat(
+        option: &str,
+        reference: &str,
+        stat: impl FnOnce(&str) -> io::Result<Metadata>,
+    ) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        let letters: Vec<char> = option.trim_start_matches("-newer").chars().collect();
+        if letters.len() != 2 {
+            return Err(From::from(format!("Unrecognized flag: '{}'", option)));
+        }
+        if letters[0] == 't' {
+            return Err(From::from(format!(
+                "{}: t (a literal time) is only valid as the second letter of \
+                 -newerXY, e.g. -newer{}t",
+                option, letters[1]
+            )));
+        }
+        let file_time_type = FileTimeType::from_newer_letter(option, letters[0])?;
+        if letters[1] == 't' && reference == "now" {
+            return Ok(Box::new(NewerMatcher::new_since_start(file_time_type)));
+        }
+        let given_time = if letters[1] == 't' {
+            parse_date(reference)?
+        } else {
+            let reference_time_type = FileTimeType::from_newer_letter(option, letters[1])?;
+            reference_time_type.get_file_time(stat(reference)?)?
+        };
+        Ok(Box::new(NewerMatcher::new_with_time(
+            file_time_type,
+            given_time,
+        )))
     }
 
     /// Implementation of matches that returns a result, allowing use to use try!
     /// to deal with the errors.
-    fn matches_impl(&self, file_info: &DirEntry) -> Result<bool, Box<dyn Error>> {
-        let this_time = file_info.metadata()?.modified()?;
-        // duration_since returns an Ok duration if this_time <= given_modification_time
+    fn matches_impl(
+        &self,
+        file_info: &DirEntry,
+        matcher_io: &MatcherIO,
+    ) -> Result<bool, Box<dyn Error>> {
+        let this_time = self
+            .file_time_type
+            .get_file_time(matcher_io.metadata(file_info)?)?;
+        let given_time = self.given_time.unwrap_or_else(|| matcher_io.now());
+        // duration_since returns an Ok duration if this_time <= given_time
         // and returns an Err (with a duration) otherwise. So if this_time >
-        // given_modification_time (in which case we want to return true) then
+        // given_time (in which case we want to return true) then
         // duration_since will return an error.
-        Ok(self
-            .given_modification_time
-            .duration_since(this_time)
-            .is_err())
+        Ok(given_time.duration_since(this_time).is_err())
     }
 }
 
 impl Matcher for NewerMatcher {
-    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
-        match self.matches_impl(file_info) {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        match self.matches_impl(file_info, matcher_io) {
             Err(e) => {
                 writeln!(
                     &mut stderr(),
-                    "Error getting modification time for {}: {}",
+                    "Error getting {} time for {}: {}",
+                    self.file_time_type.name(),
                     file_info.path().to_string_lossy(),
                     e
                 )
                 .unwrap();
+                matcher_io.mark_error();
                 false
             }
             Ok(t) => t,
@@ -64,19 +155,162 @@ impl Matcher for NewerMatcher {
     }
 }
 
+/// Converts a (possibly negative) number of seconds and nanoseconds since the
+/// epoch into a SystemTime.
+fn time_from_epoch(seconds: i64, nanoseconds: u32) -> SystemTime {
+    if seconds >= 0 {
+        UNIX_EPOCH + Duration::new(seconds as u64, nanoseconds)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs()) + Duration::new(0, nanoseconds)
+    }
+}
+
+/// Number of days between 1970-01-01 and the given (proleptic Gregorian)
+/// date. See http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Converts a broken-down local time into seconds since the epoch. mktime
+/// behaves as if tzset had been called first, so this honours TZ (falling
+/// back to /etc/localtime, as date does).
+#[cfg(unix)]
+fn local_time_to_epoch(year: i64, month: i64, day: i64, hms: (i64, i64, i64)) -> Option<i64> {
+    // safe: tm is plain old data, and all-zeroes is a valid value for it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = (year - 1900) as libc::c_int;
+    tm.tm_mon = (month - 1) as libc::c_int;
+    tm.tm_mday = day as libc::c_int;
+    tm.tm_hour = hms.0 as libc::c_int;
+    tm.tm_min = hms.1 as libc::c_int;
+    tm.tm_sec = hms.2 as libc::c_int;
+    // let mktime work out whether daylight saving applies
+    tm.tm_isdst = -1;
+    match unsafe { libc::mktime(&mut tm) } {
+        -1 => None,
+        // time_t isn't 64 bits everywhere
+        #[allow(clippy::unnecessary_cast)]
+        t => Some(t as i64),
+    }
+}
+
+/// Without mktime we've no way of knowing the local timezone, so treat local
+/// times as UTC.
+#[cfg(not(unix))]
+fn local_time_to_epoch(year: i64, month: i64, day: i64, hms: (i64, i64, i64)) -> Option<i64> {
+    Some(days_from_civil(year, month, day) * SECONDS_PER_DAY + hms.0 * 3600 + hms.1 * 60 + hms.2)
+}
+
+/// Parses the literal time given to -newerXt. Accepts @SECONDS_SINCE_EPOCH,
+/// or an ISO 8601 date with an optional time (either of which may have a
+/// trailing Z or UTC). Times without Z/UTC are interpreted as local times,
+/// in the timezone given by TZ.
+fn parse_date(date: &str) -> Result<SystemTime, Box<dyn Error>> {
+    let invalid = || -> Box<dyn Error> {
+        From::from(format!(
+            "I cannot figure out how to interpret '{}' as a date or time",
+            date
+        ))
+    };
+
+    if let Some(seconds) = date.strip_prefix('@') {
+        return Ok(time_from_epoch(seconds.parse().map_err(|_| invalid())?, 0));
+    }
+
+    // safe to unwrap as the regex is a compile-time constant.
+    let re = Regex::new(
+        r"^(-?\d{4})-(\d{2})-(\d{2})(?:[T ](\d{2}):(\d{2})(?::(\d{2})(?:\.(\d{1,9}))?)?)? *(Z|UTC)?$",
+    )
+    .unwrap();
+    let captures = re.captures(date.trim()).ok_or_else(invalid)?;
+    let number = |index: usize| -> i64 {
+        // safe to unwrap: the regex only matches digits here
+        captures
+            .get(index)
+            .map_or(0, |m| m.as_str().parse().unwrap())
+    };
+    let (year, month, day) = (number(1), number(2), number(3));
+    let (hour, minute, second) = (number(4), number(5), number(6));
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+    let nanoseconds = captures.get(7).map_or(0, |m| {
+        // pad to 9 digits, so ".5" is 500000000ns
+        format!("{:0<9}", m.as_str()).parse().unwrap()
+    });
+
+    let seconds = if captures.get(8).is_some() {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second
+    } else {
+        local_time_to_epoch(year, month, day, (hour, minute, second)).ok_or_else(invalid)?
+    };
+    Ok(time_from_epoch(seconds, nanoseconds))
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum FileTimeType {
     Accessed,
     Created,
     Modified,
+    /// The inode change time.
+    Changed,
 }
 
 impl FileTimeType {
+    /// What to call the time in messages, e.g. "Error getting modification
+    /// time".
+    fn name(self) -> &'static str {
+        match self {
+            FileTimeType::Accessed => "access",
+            FileTimeType::Created => "birth",
+            FileTimeType::Modified => "modification",
+            FileTimeType::Changed => "change",
+        }
+    }
+
     fn get_file_time(self, metadata: Metadata) -> std::io::Result<SystemTime> {
         match self {
             FileTimeType::Accessed => metadata.accessed(),
             FileTimeType::Created => metadata.created(),
             FileTimeType::Modified => metadata.modified(),
+            #[cfg(unix)]
+            FileTimeType::Changed => {
+                use std::os::unix::fs::MetadataExt;
+                Ok(time_from_epoch(
+                    metadata.ctime(),
+                    metadata.ctime_nsec() as u32,
+                ))
+            }
+            #[cfg(not(unix))]
+            FileTimeType::Changed => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "change time is not available on this platform",
+            )),
+        }
+    }
+
+    /// Converts one of the XY letters of -newerXY into a FileTimeType.
+    fn from_newer_letter(option: &str, letter: char) -> Result<FileTimeType, Box<dyn Error>> {
+        match letter {
+            'a' => Ok(FileTimeType::Accessed),
+            'B' => Ok(FileTimeType::Created),
+            'c' => Ok(FileTimeType::Changed),
+            'm' => Ok(FileTimeType::Modified),
+            _ => Err(From::from(format!(
+                "{}: invalid time type '{}'. Only allowed values are a, B, c, m \
+                 or (as the second letter) t",
+                option, letter
+            ))),
         }
     }
 }
@@ -90,16 +324,17 @@ pub struct FileTimeMatcher {
 
 impl Matcher for FileTimeMatcher {
     fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
-        match self.matches_impl(file_info, matcher_io.now()) {
+        match self.matches_impl(file_info, matcher_io) {
             Err(e) => {
                 writeln!(
                     &mut stderr(),
-                    "Error getting {:?} time for {}: {}",
-                    self.file_time_type,
+                    "Error getting {} time for {}: {}",
+                    self.file_time_type.name(),
                     file_info.path().to_string_lossy(),
                     e
                 )
                 .unwrap();
+                matcher_io.mark_error();
                 false
             }
             Ok(t) => t,
@@ -110,8 +345,15 @@ impl Matcher for FileTimeMatcher {
 impl FileTimeMatcher {
     /// Implementation of matches that returns a result, allowing use to use try!
     /// to deal with the errors.
-    fn matches_impl(&self, file_info: &DirEntry, now: SystemTime) -> Result<bool, Box<dyn Error>> {
-        let this_time = self.file_time_type.get_file_time(file_info.metadata()?)?;
+    fn matches_impl(
+        &self,
+        file_info: &DirEntry,
+        matcher_io: &MatcherIO,
+    ) -> Result<bool, Box<dyn Error>> {
+        let now = matcher_io.now();
+        let this_time = self
+            .file_time_type
+            .get_file_time(matcher_io.metadata(file_info)?)?;
         let mut is_negative = false;
         // durations can't be negative. So duration_since returns a duration
         // wrapped in an error if now < this_time.
@@ -144,12 +386,85 @@ impl FileTimeMatcher {
     }
 }
 
+/// This matcher checks whether a file was modified within the given duration
+/// (e.g. "7d") of now: a friendlier alternative to -mtime/-mmin arithmetic.
+pub struct WithinMatcher {
+    duration: Duration,
+}
+
+impl WithinMatcher {
+    /// Accepts a whole number followed by an optional unit: s (seconds, the
+    /// default), m (minutes), h (hours), d (days) or w (weeks).
+    pub fn new(duration: &str) -> Result<WithinMatcher, Box<dyn Error>> {
+        let invalid = || -> Box<dyn Error> {
+            From::from(format!(
+                "invalid argument '{}' to -within. Expected a number followed \
+                 by an optional unit: s, m, h, d or w",
+                duration
+            ))
+        };
+        let digits_end = duration
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(duration.len());
+        let (number, unit) = duration.split_at(digits_end);
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+        let multiplier = match unit {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => SECONDS_PER_DAY as u64,
+            "w" => 7 * SECONDS_PER_DAY as u64,
+            _ => return Err(invalid()),
+        };
+        let seconds = number.checked_mul(multiplier).ok_or_else(invalid)?;
+        Ok(WithinMatcher {
+            duration: Duration::from_secs(seconds),
+        })
+    }
+
+    pub fn new_box(duration: &str) -> Result<Box<dyn Matcher>, Box<dyn Error>> {
+        Ok(Box::new(WithinMatcher::new(duration)?))
+    }
+
+    fn matches_impl(
+        &self,
+        file_info: &DirEntry,
+        matcher_io: &MatcherIO,
+    ) -> Result<bool, Box<dyn Error>> {
+        let this_time = matcher_io.metadata(file_info)?.modified()?;
+        Ok(match matcher_io.now().duration_since(this_time) {
+            Ok(age) => age <= self.duration,
+            // modified in the future, which is certainly within the duration
+            Err(_) => true,
+        })
+    }
+}
+
+impl Matcher for WithinMatcher {
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        match self.matches_impl(file_info, matcher_io) {
+            Err(e) => {
+                writeln!(
+                    &mut stderr(),
+                    "Error getting modification time for {}: {}",
+                    file_info.path().to_string_lossy(),
+                    e
+                )
+                .unwrap();
+                matcher_io.mark_error();
+                false
+            }
+            Ok(t) => t,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs::{File, OpenOptions};
+    use std::fs::{self, File, OpenOptions};
     use std::io::{Read, Write};
     use std::thread;
-    use std::time::{Duration, SystemTime};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
     use tempdir::TempDir;
     use walkdir::DirEntry;
 
@@ -169,7 +484,7 @@ mod tests {
         let new_file_name = "newFile";
         File::create(temp_dir.path().join(new_file_name)).expect("create temp file");
 
-        let new_file = get_dir_entry_for(&temp_dir_path, &new_file_name);
+        let new_file = get_dir_entry_for(&temp_dir_path, new_file_name);
 
         let matcher_for_new =
             NewerMatcher::new(&temp_dir.path().join(new_file_name).to_string_lossy()).unwrap();
@@ -190,6 +505,278 @@ mod tests {
         );
     }
 
+    #[test]
+    fn newer_xy_matcher() {
+        let old_file = get_dir_entry_for("test_data", "simple");
+        let temp_dir = TempDir::new("newer_xy_matcher").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        let new_file_path = temp_dir.path().join("newFile");
+        File::create(&new_file_path).expect("create temp file");
+        let new_file = get_dir_entry_for(&temp_dir_path, "newFile");
+        let deps = FakeDependencies::new();
+
+        for date in &[
+            "@0",
+            "2000-01-01",
+            "2000-01-01 12:34",
+            "2000-01-01T12:34:56.5Z",
+        ] {
+            let matcher = NewerMatcher::new_box_xy("-newermt", date).unwrap();
+            assert!(
+                matcher.matches(&new_file, &mut deps.new_matcher_io()),
+                "new file should be newer than {}",
+                date
+            );
+        }
+        let matcher = NewerMatcher::new_box_xy("-newermt", "2999-01-01").unwrap();
+        assert!(!matcher.matches(&new_file, &mut deps.new_matcher_io()));
+
+        let matcher =
+            NewerMatcher::new_box_xy("-newercm", &old_file.path().to_string_lossy()).unwrap();
+        assert!(matcher.matches(&new_file, &mut deps.new_matcher_io()));
+        let matcher =
+            NewerMatcher::new_box_xy("-newermm", &new_file_path.to_string_lossy()).unwrap();
+        assert!(!matcher.matches(&old_file, &mut deps.new_matcher_io()));
+    }
+
+    #[test]
+    fn newer_xy_matcher_now() {
+        let temp_dir = TempDir::new("newer_xy_matcher_now").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        File::create(temp_dir.path().join("newFile")).expect("create temp file");
+        let new_file = get_dir_entry_for(&temp_dir_path, "newFile");
+        let new_files_mtime = new_file.metadata().unwrap().modified().unwrap();
+        let old_file = get_dir_entry_for("test_data", "simple");
+        let matcher = NewerMatcher::new_box_xy("-newermt", "now").unwrap();
+        let mut deps = FakeDependencies::new();
+
+        deps.set_time(new_files_mtime + Duration::from_secs(1));
+        assert!(
+            !matcher.matches(&new_file, &mut deps.new_matcher_io()),
+            "file modified before find started shouldn't be newer than now"
+        );
+        assert!(!matcher.matches(&old_file, &mut deps.new_matcher_io()));
+
+        deps.set_time(new_files_mtime - Duration::from_secs(1));
+        assert!(
+            matcher.matches(&new_file, &mut deps.new_matcher_io()),
+            "file modified after find started should be newer than now"
+        );
+    }
+
+    #[test]
+    fn newer_xy_matcher_bad_args() {
+        for (option, reference, message) in &[
+            ("-newertm", "2000-01-01", "only valid as the second letter"),
+            ("-newerxm", "test_data", "invalid time type 'x'"),
+            ("-newermx", "test_data", "invalid time type 'x'"),
+            ("-newermt", "yesterday-ish", "as a date or time"),
+            ("-newermt", "2000-13-01", "as a date or time"),
+            ("-newermt", "@1.5", "as a date or time"),
+        ] {
+            match NewerMatcher::new_box_xy(option, reference) {
+                Err(e) => assert!(
+                    e.to_string().contains(message),
+                    "bad description for {} {}: {}",
+                    option,
+                    reference,
+                    e
+                ),
+                Ok(_) => panic!("{} {} should fail", option, reference),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_utc_dates() {
+        assert_eq!(
+            parse_date("@-86400").unwrap(),
+            UNIX_EPOCH - Duration::from_secs(86400)
+        );
+        assert_eq!(
+            parse_date("1970-01-02Z").unwrap(),
+            UNIX_EPOCH + Duration::from_secs(86400)
+        );
+        assert_eq!(
+            parse_date("2001-09-09 01:46:40.25 UTC").unwrap(),
+            UNIX_EPOCH + Duration::new(1_000_000_000, 250_000_000)
+        );
+        assert_eq!(
+            parse_date("1969-12-31T23:59:59Z").unwrap(),
+            UNIX_EPOCH - Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn within_matcher() {
+        let file = get_dir_entry_for("test_data", "simple");
+        let files_mtime = file.metadata().unwrap().modified().unwrap();
+        let matcher = WithinMatcher::new("1h").unwrap();
+        let mut deps = FakeDependencies::new();
+
+        deps.set_time(files_mtime + Duration::from_secs(30 * 60));
+        assert!(
+            matcher.matches(&file, &mut deps.new_matcher_io()),
+            "file modified 30 minutes ago should be within 1h"
+        );
+        deps.set_time(files_mtime + Duration::from_secs(2 * 60 * 60));
+        assert!(
+            !matcher.matches(&file, &mut deps.new_matcher_io()),
+            "file modified 2 hours ago shouldn't be within 1h"
+        );
+        deps.set_time(files_mtime - Duration::from_secs(1));
+        assert!(
+            matcher.matches(&file, &mut deps.new_matcher_io()),
+            "future-modified file should be within 1h"
+        );
+    }
+
+    #[test]
+    fn within_matcher_units() {
+        for (arg, seconds) in &[
+            ("90", 90),
+            ("90s", 90),
+            ("30m", 30 * 60),
+            ("2h", 2 * 60 * 60),
+            ("7d", 7 * 24 * 60 * 60),
+            ("1w", 7 * 24 * 60 * 60),
+        ] {
+            assert_eq!(
+                WithinMatcher::new(arg).unwrap().duration,
+                Duration::from_secs(*seconds),
+                "for {}",
+                arg
+            );
+        }
+        for arg in &["", "h", "1y", "-1d", "1.5h", "99999999999999999999w"] {
+            assert!(WithinMatcher::new(arg).is_err(), "{} should fail", arg);
+        }
+    }
+
+    #[test]
+    fn newer_matcher_sub_second_precision() {
+        let temp_dir = TempDir::new("newer_matcher_sub_second_precision").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        let base = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+        for (name, offset_ms) in &[("before", 250), ("reference", 500), ("after", 750)] {
+            File::create(temp_dir.path().join(name))
+                .and_then(|f| f.set_modified(base + Duration::from_millis(*offset_ms)))
+                .expect("create temp file");
+        }
+        let before = get_dir_entry_for(&temp_dir_path, "before");
+        let after = get_dir_entry_for(&temp_dir_path, "after");
+        if before.metadata().unwrap().modified().unwrap() == base {
+            // the file system only stores whole seconds
+            return;
+        }
+        let deps = FakeDependencies::new();
+
+        let reference = temp_dir.path().join("reference");
+        let reference = reference.to_string_lossy();
+        for matcher in &[
+            Box::new(NewerMatcher::new(&reference).unwrap()) as Box<dyn Matcher>,
+            NewerMatcher::new_box_xy("-newermm", &reference).unwrap(),
+        ] {
+            assert!(matcher.matches(&after, &mut deps.new_matcher_io()));
+            assert!(!matcher.matches(&before, &mut deps.new_matcher_io()));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn newer_matcher_proc_fd_reference() {
+        use std::os::unix::io::AsRawFd;
+
+        // /proc/self/fd/N is a "magic" symlink to whatever N has open, which
+        // fs::metadata (being a stat rather than an lstat) follows
+        let temp_dir = TempDir::new("newer_matcher_proc_fd_reference").unwrap();
+        let reference = File::create(temp_dir.path().join("reference")).unwrap();
+        reference
+            .set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+            .unwrap();
+        let fd_path = format!("/proc/self/fd/{}", reference.as_raw_fd());
+        if fs::symlink_metadata(&fd_path).is_err() {
+            // no /proc mounted
+            return;
+        }
+        let file = get_dir_entry_for("test_data", "simple");
+        let deps = FakeDependencies::new();
+
+        for matcher in &[
+            Box::new(NewerMatcher::new(&fd_path).unwrap()) as Box<dyn Matcher>,
+            NewerMatcher::new_box_xy("-newermm", &fd_path).unwrap(),
+        ] {
+            let mut matcher_io = deps.new_matcher_io();
+            assert!(matcher.matches(&file, &mut matcher_io));
+            assert!(!matcher_io.had_error());
+        }
+    }
+
+    #[test]
+    fn time_matchers_with_vanished_file() {
+        // the file goes away between being found and being stat'd
+        let temp_dir = TempDir::new("time_matchers_with_vanished_file").unwrap();
+        File::create(temp_dir.path().join("vanished")).unwrap();
+        let file = get_dir_entry_for(&temp_dir.path().to_string_lossy(), "vanished");
+        fs::remove_file(temp_dir.path().join("vanished")).unwrap();
+        let deps = FakeDependencies::new();
+
+        for matcher in &[
+            Box::new(NewerMatcher::new("test_data/simple").unwrap()) as Box<dyn Matcher>,
+            NewerMatcher::new_box_xy("-newermt", "@0").unwrap(),
+            FileTimeMatcher::new_box(FileTimeType::Modified, ComparableValue::MoreThan(0)),
+            WithinMatcher::new_box("1w").unwrap(),
+        ] {
+            let mut matcher_io = deps.new_matcher_io();
+            assert!(!matcher.matches(&file, &mut matcher_io));
+            assert!(matcher_io.had_error());
+        }
+    }
+
+    #[test]
+    fn pre_epoch_times() {
+        let temp_dir = TempDir::new("pre_epoch_times").unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        let old_file_path = temp_dir.path().join("oldFile");
+        let pre_epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(365 * 24 * 60 * 60);
+        File::create(&old_file_path)
+            .and_then(|f| f.set_modified(pre_epoch))
+            .expect("create pre-epoch file");
+        File::create(temp_dir.path().join("newFile")).expect("create temp file");
+
+        let old_file = get_dir_entry_for(&temp_dir_path, "oldFile");
+        if old_file.metadata().unwrap().modified().unwrap() != pre_epoch {
+            // not every file system can store times before 1970
+            return;
+        }
+        let new_file = get_dir_entry_for(&temp_dir_path, "newFile");
+        let deps = FakeDependencies::new();
+
+        let matcher_for_old = NewerMatcher::new(&old_file_path.to_string_lossy()).unwrap();
+        assert!(
+            matcher_for_old.matches(&new_file, &mut deps.new_matcher_io()),
+            "new_file should be newer than a pre-epoch file"
+        );
+        assert!(
+            !matcher_for_old.matches(&old_file, &mut deps.new_matcher_io()),
+            "pre-epoch file shouldn't be newer than itself"
+        );
+
+        let matcher_for_new =
+            NewerMatcher::new(&temp_dir.path().join("newFile").to_string_lossy()).unwrap();
+        assert!(
+            !matcher_for_new.matches(&old_file, &mut deps.new_matcher_io()),
+            "pre-epoch file shouldn't be newer than new_file"
+        );
+
+        let more_than_a_year_matcher =
+            FileTimeMatcher::new(FileTimeType::Modified, ComparableValue::MoreThan(365));
+        assert!(
+            more_than_a_year_matcher.matches(&old_file, &mut deps.new_matcher_io()),
+            "pre-epoch file should be more than a year old"
+        );
+    }
+
     #[test]
     fn file_time_matcher() {
         // this file should already exist
@@ -266,7 +853,7 @@ mod tests {
 
         // set "now" to a second before the file was modified (e.g. the file was
         // modified after find started running
-        deps.set_time(files_mtime - Duration::new(1 as u64, 0));
+        deps.set_time(files_mtime - Duration::new(1_u64, 0));
         assert!(
             !exactly_one_day_matcher.matches(&file, &mut deps.new_matcher_io()),
             "future-modified file shouldn'1 match exactly 1 day old"
@@ -312,7 +899,7 @@ mod tests {
                 .write(true)
                 .open(&foo_path)
                 .expect("open temp file");
-            let _ = f.write(&mut buffer);
+            let _ = f.write(&buffer);
         }
 
         thread::sleep(Duration::from_secs(2));
@@ -356,17 +943,25 @@ mod tests {
             let mut deps = FakeDependencies::new();
             deps.set_time(file_time);
             assert!(
-                matcher.matches(&file_info, &mut deps.new_matcher_io()),
+                matcher.matches(file_info, &mut deps.new_matcher_io()),
                 "{:?} time matcher should match",
                 file_time_type
             );
 
             deps.set_time(file_time - Duration::from_secs(1));
             assert!(
-                !matcher.matches(&file_info, &mut deps.new_matcher_io()),
+                !matcher.matches(file_info, &mut deps.new_matcher_io()),
                 "{:?} time matcher shouldn't match a second before",
                 file_time_type
             );
         }
     }
+
+    #[test]
+    fn file_time_type_names() {
+        assert_eq!(FileTimeType::Accessed.name(), "access");
+        assert_eq!(FileTimeType::Created.name(), "birth");
+        assert_eq!(FileTimeType::Modified.name(), "modification");
+        assert_eq!(FileTimeType::Changed.name(), "change");
+    }
 }
@@ -13,50 +13,101 @@ use std::os::unix::fs::FileTypeExt;
 
 use super::{Matcher, MatcherIO};
 
-/// This matcher checks the type of the file.
+/// This matcher checks the type of the file. Like GNU find, it accepts a
+/// comma-separated list of types (e.g. "f,d"), matching any of them.
 pub struct TypeMatcher {
-    file_type_fn: fn(&FileType) -> bool,
+    file_type_fns: Vec<FileTypeFn>,
+    /// Set by the non-standard "w" type. Whiteouts can't be recognised from
+    /// the file type alone, so they're checked separately.
+    match_whiteouts: bool,
+}
+
+type FileTypeFn = fn(&FileType) -> bool;
+
+/// The error for a type letter we don't know, suggesting the right letter if
+/// it's just in the wrong case (e.g. F rather than f).
+fn unrecognised_type_letter(type_string: &str) -> Box<dyn Error> {
+    let lower = type_string.to_lowercase();
+    if lower != type_string && (lower == "w" || parse_type_letter(&lower).is_ok()) {
+        From::from(format!(
+            "Unrecognised type argument {} (did you mean -type {}?)",
+            type_string, lower
+        ))
+    } else {
+        From::from(format!("Unrecognised type argument {}", type_string))
+    }
+}
+
+/// Converts a single type letter into the function that checks for it.
+fn parse_type_letter(type_string: &str) -> Result<FileTypeFn, Box<dyn Error>> {
+    #[cfg(unix)]
+    let function = match type_string {
+        "f" => FileType::is_file,
+        "d" => FileType::is_dir,
+        "l" => FileType::is_symlink,
+        "b" => FileType::is_block_device,
+        "c" => FileType::is_char_device,
+        "p" => FileType::is_fifo, // named pipe (FIFO)
+        "s" => FileType::is_socket,
+        // D: door (Solaris)
+        "D" => {
+            return Err(From::from(format!(
+                "Type argument {} not supported yet",
+                type_string
+            )))
+        }
+        _ => return Err(unrecognised_type_letter(type_string)),
+    };
+    #[cfg(not(unix))]
+    let function = match type_string {
+        "f" => FileType::is_file,
+        "d" => FileType::is_dir,
+        "l" => FileType::is_symlink,
+        _ => return Err(unrecognised_type_letter(type_string)),
+    };
+    Ok(function)
 }
 
 impl TypeMatcher {
     pub fn new(type_string: &str) -> Result<TypeMatcher, Box<dyn Error>> {
-        #[cfg(unix)]
-        let function = match type_string {
-            "f" => FileType::is_file,
-            "d" => FileType::is_dir,
-            "l" => FileType::is_symlink,
-            "b" => FileType::is_block_device,
-            "c" => FileType::is_char_device,
-            "p" => FileType::is_fifo, // named pipe (FIFO)
-            "s" => FileType::is_socket,
-            // D: door (Solaris)
-            "D" => {
+        if type_string.is_empty() {
+            return Err(From::from(
+                "Arguments to -type should contain at least one letter",
+            ));
+        }
+        let mut seen = vec![];
+        let mut file_type_fns = vec![];
+        let mut match_whiteouts = false;
+        for letter in type_string.split(',') {
+            if letter.is_empty() {
                 return Err(From::from(format!(
-                    "Type argument {} not supported yet",
+                    "Missing file type in the argument list to -type: '{}'",
                     type_string
-                )))
+                )));
             }
-            _ => {
+            if letter.chars().count() > 1 {
                 return Err(From::from(format!(
-                    "Unrecognised type argument {}",
+                    "Must separate multiple arguments to -type using: ',' \
+                     (e.g. -type f,d rather than -type {})",
                     type_string
-                )))
+                )));
             }
-        };
-        #[cfg(not(unix))]
-        let function = match type_string {
-            "f" => FileType::is_file,
-            "d" => FileType::is_dir,
-            "l" => FileType::is_symlink,
-            _ => {
+            if seen.contains(&letter) {
                 return Err(From::from(format!(
-                    "Unrecognised type argument {}",
-                    type_string
-                )))
+                    "Duplicate file type '{}' in the argument list to -type",
+                    letter
+                )));
             }
-        };
+            seen.push(letter);
+            if letter == "w" {
+                match_whiteouts = true;
+            } else {
+                file_type_fns.push(parse_type_letter(letter)?);
+            }
+        }
         Ok(TypeMatcher {
-            file_type_fn: function,
+            file_type_fns,
+            match_whiteouts,
         })
     }
 
@@ -65,25 +116,39 @@ impl TypeMatcher {
     }
 }
 
+/// Checks for a whiteout: the marker a union file system (e.g. overlayfs)
+/// leaves in its upper layer to hide a file from a lower one. Linux
+/// represents these as character devices with device number 0/0. Elsewhere
+/// std gives us no way of seeing them (e.g. the BSDs' DT_WHT) so nothing
+/// matches.
+#[cfg(target_os = "linux")]
+fn is_whiteout(file_info: &DirEntry, matcher_io: &MatcherIO) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    file_info.file_type().is_char_device()
+        && matcher_io
+            .metadata(file_info)
+            .map(|metadata| metadata.rdev() == 0)
+            .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_whiteout(_file_info: &DirEntry, _matcher_io: &MatcherIO) -> bool {
+    false
+}
+
 impl Matcher for TypeMatcher {
-    fn matches(&self, file_info: &DirEntry, _: &mut MatcherIO) -> bool {
-        (self.file_type_fn)(&file_info.file_type())
+    fn matches(&self, file_info: &DirEntry, matcher_io: &mut MatcherIO) -> bool {
+        let file_type = file_info.file_type();
+        self.file_type_fns.iter().any(|f| f(&file_type))
+            || (self.match_whiteouts && is_whiteout(file_info, matcher_io))
     }
 }
 #[cfg(test)]
-
 mod tests {
     use super::*;
-    use crate::find::matchers::tests::get_dir_entry_for;
+    use crate::find::matchers::tests::{create_test_links, get_dir_entry_for};
     use crate::find::matchers::Matcher;
     use crate::find::tests::FakeDependencies;
-    use std::io::ErrorKind;
-
-    #[cfg(unix)]
-    use std::os::unix::fs::symlink;
-
-    #[cfg(windows)]
-    use std::os::windows::fs::{symlink_dir, symlink_file};
 
     #[test]
     fn file_type_matcher() {
@@ -91,7 +156,7 @@ mod tests {
         let dir = get_dir_entry_for("test_data", "simple");
         let deps = FakeDependencies::new();
 
-        let matcher = TypeMatcher::new(&"f".to_string()).unwrap();
+        let matcher = TypeMatcher::new("f").unwrap();
         assert!(!matcher.matches(&dir, &mut deps.new_matcher_io()));
         assert!(matcher.matches(&file, &mut deps.new_matcher_io()));
     }
@@ -102,41 +167,14 @@ mod tests {
         let dir = get_dir_entry_for("test_data", "simple");
         let deps = FakeDependencies::new();
 
-        let matcher = TypeMatcher::new(&"d".to_string()).unwrap();
+        let matcher = TypeMatcher::new("d").unwrap();
         assert!(matcher.matches(&dir, &mut deps.new_matcher_io()));
         assert!(!matcher.matches(&file, &mut deps.new_matcher_io()));
     }
 
-    // git does not translate links (in test_data) to Windows links
-    // so we have to create links in test
     #[test]
     fn link_type_matcher() {
-        #[cfg(unix)]
-        let _ = {
-            if let Err(e) = symlink("abbbc", "test_data/links/link-f") {
-                if e.kind() != ErrorKind::AlreadyExists {
-                    panic!("Failed to create sym link: {:?}", e);
-                }
-            }
-            if let Err(e) = symlink("subdir", "test_data/links/link-d") {
-                if e.kind() != ErrorKind::AlreadyExists {
-                    panic!("Failed to create sym link: {:?}", e);
-                }
-            }
-        };
-        #[cfg(windows)]
-        let _ = {
-            if let Err(e) = symlink_file("abbbc", "test_data/links/link-f") {
-                if e.kind() != ErrorKind::AlreadyExists {
-                    panic!("Failed to create sym link: {:?}", e);
-                }
-            }
-            if let Err(e) = symlink_dir("subdir", "test_data/links/link-d") {
-                if e.kind() != ErrorKind::AlreadyExists {
-                    panic!("Failed to create sym link: {:?}", e);
-                }
-            }
-        };
+        create_test_links();
 
         let link_f = get_dir_entry_for("test_data/links", "link-f");
         let link_d = get_dir_entry_for("test_data/links", "link-d");
@@ -144,7 +182,7 @@ mod tests {
         let dir = get_dir_entry_for("test_data", "links");
         let deps = FakeDependencies::new();
 
-        let matcher = TypeMatcher::new(&"l".to_string()).unwrap();
+        let matcher = TypeMatcher::new("l").unwrap();
         assert!(!matcher.matches(&dir, &mut deps.new_matcher_io()));
         assert!(!matcher.matches(&file, &mut deps.new_matcher_io()));
         assert!(matcher.matches(&link_f, &mut deps.new_matcher_io()));
@@ -159,15 +197,101 @@ mod tests {
         let deps = FakeDependencies::new();
 
         for typ in ["b", "c", "p", "s"].iter() {
-            let matcher = TypeMatcher::new(&typ.to_string()).unwrap();
+            let matcher = TypeMatcher::new(typ).unwrap();
             assert!(!matcher.matches(&dir, &mut deps.new_matcher_io()));
             assert!(!matcher.matches(&file, &mut deps.new_matcher_io()));
         }
     }
 
+    #[test]
+    fn type_list_matcher() {
+        let file = get_dir_entry_for("test_data/simple", "abbbc");
+        let dir = get_dir_entry_for("test_data", "simple");
+        let deps = FakeDependencies::new();
+
+        let matcher = TypeMatcher::new("f,d").unwrap();
+        assert!(matcher.matches(&dir, &mut deps.new_matcher_io()));
+        assert!(matcher.matches(&file, &mut deps.new_matcher_io()));
+
+        let matcher = TypeMatcher::new("l,d").unwrap();
+        assert!(matcher.matches(&dir, &mut deps.new_matcher_io()));
+        assert!(!matcher.matches(&file, &mut deps.new_matcher_io()));
+    }
+
+    #[test]
+    fn cant_create_with_bad_type_list() {
+        for (arg, message) in &[
+            ("", "at least one letter"),
+            ("fd", "Must separate multiple arguments to -type using: ','"),
+            ("f,", "Missing file type"),
+            (",f", "Missing file type"),
+            ("f,,d", "Missing file type"),
+            ("f,f", "Duplicate file type 'f'"),
+            ("f,x", "Unrecognised type argument x"),
+            ("F", "Unrecognised type argument F (did you mean -type f?)"),
+            ("f,L", "(did you mean -type l?)"),
+        ] {
+            match TypeMatcher::new(arg) {
+                Err(e) => assert!(
+                    e.to_string().contains(message),
+                    "bad description for '{}': {}",
+                    arg,
+                    e
+                ),
+                Ok(_) => panic!("-type '{}' should fail", arg),
+            }
+        }
+    }
+
+    #[test]
+    fn whiteout_type_matcher() {
+        let deps = FakeDependencies::new();
+        let matcher = TypeMatcher::new("w").unwrap();
+        for entry in walkdir::WalkDir::new("test_data") {
+            let entry = entry.unwrap();
+            assert!(
+                !matcher.matches(&entry, &mut deps.new_matcher_io()),
+                "{} isn't a whiteout",
+                entry.path().display()
+            );
+        }
+        #[cfg(unix)]
+        {
+            // /dev/null is a character device, but not a whiteout
+            let null = get_dir_entry_for("/dev", "null");
+            assert!(!matcher.matches(&null, &mut deps.new_matcher_io()));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn whiteout_type_matcher_matches_overlayfs_whiteouts() {
+        use std::ffi::CString;
+        use tempdir::TempDir;
+
+        let temp_dir = TempDir::new("whiteout_type_matcher").unwrap();
+        let whiteout = temp_dir.path().join("whiteout");
+        let path = CString::new(whiteout.to_string_lossy().as_bytes()).unwrap();
+        if unsafe { libc::mknod(path.as_ptr(), libc::S_IFCHR | 0o600, 0) } != 0 {
+            // making device nodes needs privileges we may not have
+            return;
+        }
+        let entry = get_dir_entry_for(&temp_dir.path().to_string_lossy(), "whiteout");
+        let deps = FakeDependencies::new();
+        assert!(TypeMatcher::new("w")
+            .unwrap()
+            .matches(&entry, &mut deps.new_matcher_io()));
+        assert!(TypeMatcher::new("f,w")
+            .unwrap()
+            .matches(&entry, &mut deps.new_matcher_io()));
+        assert!(!TypeMatcher::new("f")
+            .unwrap()
+            .matches(&entry, &mut deps.new_matcher_io()));
+    }
+
     #[test]
     fn cant_create_with_invalid_pattern() {
-        let result = TypeMatcher::new(&"xxx".to_string());
+        let result = TypeMatcher::new("xxx");
         assert!(result.is_err());
     }
 }
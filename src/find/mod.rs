@@ -7,17 +7,48 @@
 pub mod matchers;
 
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::error::Error;
-use std::io::{stderr, stdout, Write};
+use std::fs::{self, Metadata};
+use std::io::{self, stderr, stdout, IsTerminal, Write};
+use std::path::Path;
 use std::rc::Rc;
-use std::time::SystemTime;
-use walkdir::WalkDir;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::{DirEntry, WalkDir};
 
 pub struct Config {
     depth_first: bool,
     min_depth: usize,
     max_depth: usize,
     sorted_output: bool,
+    reverse_sorted_output: bool,
+    dirs_first: bool,
+    files_first: bool,
+    same_file_system: bool,
+    stop_on_error: bool,
+    debug_rates: bool,
+    debug_stat: bool,
+    debug_xdev: bool,
+    exec_timeout: Option<Duration>,
+    verbose: bool,
+    /// Whether the output is a terminal, which decides some defaults (like
+    /// the quoting style).
+    output_is_terminal: bool,
+    quoting_style: matchers::QuotingStyle,
+    ignore_case: bool,
+    dry_run: bool,
+    delete_stats: bool,
+    brace_expansion: bool,
+    /// Set by the undocumented --now, so that tests don't depend on the
+    /// actual time.
+    now: Option<SystemTime>,
+    regex_type: matchers::RegexType,
+    regex_anchor: matchers::RegexAnchor,
+    ls_colors: Option<Rc<matchers::LsColors>>,
+    /// Reference files (for -newer etc.) that have already been stat'd, and
+    /// whether symlinks were followed to do it.
+    reference_metadata: HashMap<(String, bool), Metadata>,
     help_requested: bool,
 }
 
@@ -26,18 +57,62 @@ impl Default for Config {
         Config {
             depth_first: false,
             min_depth: 0,
-            max_depth: usize::max_value(),
+            max_depth: usize::MAX,
             sorted_output: false,
+            reverse_sorted_output: false,
+            dirs_first: false,
+            files_first: false,
+            same_file_system: false,
+            stop_on_error: false,
+            debug_rates: false,
+            debug_stat: false,
+            debug_xdev: false,
+            exec_timeout: None,
+            verbose: false,
+            output_is_terminal: false,
+            quoting_style: matchers::QuotingStyle::default(),
+            ignore_case: false,
+            dry_run: false,
+            delete_stats: false,
+            brace_expansion: false,
+            now: None,
+            regex_type: matchers::RegexType::default(),
+            regex_anchor: matchers::RegexAnchor::default(),
+            ls_colors: None,
+            reference_metadata: HashMap::new(),
             help_requested: false,
         }
     }
 }
 
+impl Config {
+    /// Stats a reference file for a test like -newer. Each path is only
+    /// stat'd once, however many tests refer to it, so they all see the same
+    /// times. -newer and -newerXY have always followed a symlink reference,
+    /// but others (like -samefile and -sizelike) stick to -P's semantics and
+    /// don't.
+    fn reference_metadata(&mut self, path: &str, follow: bool) -> io::Result<Metadata> {
+        let key = (path.to_string(), follow);
+        if let Some(metadata) = self.reference_metadata.get(&key) {
+            return Ok(metadata.clone());
+        }
+        let metadata = if follow {
+            fs::metadata(path)?
+        } else {
+            fs::symlink_metadata(path)?
+        };
+        self.reference_metadata.insert(key, metadata.clone());
+        Ok(metadata)
+    }
+}
+
 /// Trait that encapsulates various dependencies (output, clocks, etc.) that we
 /// might want to fake out for unit tests.
 pub trait Dependencies<'a> {
     fn get_output(&'a self) -> &'a RefCell<dyn Write>;
     fn now(&'a self) -> SystemTime;
+    /// Whether the output is a terminal (rather than e.g. a pipe or a file).
+    fn is_terminal(&'a self) -> bool;
 }
 
 /// Struct that holds the dependencies we use when run as the real executable.
@@ -69,6 +144,10 @@ impl<'a> Dependencies<'a> for StandardDependencies {
     fn now(&'a self) -> SystemTime {
         self.now
     }
+
+    fn is_terminal(&'a self) -> bool {
+        stdout().is_terminal()
+    }
 }
 
 /// The result of parsing the command-line arguments into useful forms.
@@ -79,11 +158,102 @@ struct ParsedInfo {
 }
 
 /// Function to generate a `ParsedInfo` from the strings supplied on the command-line.
-fn parse_args(args: &[&str]) -> Result<ParsedInfo, Box<dyn Error>> {
+fn parse_args<'a>(
+    args: &[&str],
+    deps: &'a dyn Dependencies<'a>,
+) -> Result<ParsedInfo, Box<dyn Error>> {
     let mut paths = vec![];
     let mut i = 0;
-    let mut config = Config::default();
+    let mut config = Config {
+        output_is_terminal: deps.is_terminal(),
+        ..Config::default()
+    };
+    let mut quoting_style = None;
 
+    while i < args.len()
+        && (args[i] == "-D"
+            || args[i] == "-v"
+            || args[i] == "--verbose"
+            || args[i] == "--ignore-case"
+            || args[i] == "--dry-run"
+            || args[i] == "--delete-stats"
+            || args[i] == "--brace-expansion"
+            || args[i] == "--now"
+            || args[i] == "--dirs-first"
+            || args[i] == "--files-first"
+            || args[i].starts_with("--quoting-style="))
+    {
+        if let Some(style) = args[i].strip_prefix("--quoting-style=") {
+            quoting_style = Some(style.parse()?);
+            i += 1;
+            continue;
+        }
+        if args[i] == "--ignore-case" {
+            config.ignore_case = true;
+            i += 1;
+            continue;
+        }
+        if args[i] == "--dry-run" {
+            config.dry_run = true;
+            i += 1;
+            continue;
+        }
+        if args[i] == "--now" {
+            if i == args.len() - 1 {
+                return Err(From::from("missing argument to --now"));
+            }
+            let seconds: u64 = args[i + 1].parse().map_err(|_| {
+                format!(
+                    "Expected a number of seconds since the epoch for --now, but got `{}'",
+                    args[i + 1]
+                )
+            })?;
+            config.now = Some(UNIX_EPOCH + Duration::from_secs(seconds));
+            i += 2;
+            continue;
+        }
+        if args[i] == "--delete-stats" {
+            config.delete_stats = true;
+            i += 1;
+            continue;
+        }
+        if args[i] == "--brace-expansion" {
+            config.brace_expansion = true;
+            i += 1;
+            continue;
+        }
+        // whichever comes last wins
+        if args[i] == "--dirs-first" || args[i] == "--files-first" {
+            config.dirs_first = args[i] == "--dirs-first";
+            config.files_first = !config.dirs_first;
+            i += 1;
+            continue;
+        }
+        if args[i] != "-D" {
+            config.verbose = true;
+            i += 1;
+            continue;
+        }
+        if i == args.len() - 1 {
+            return Err(From::from("missing argument to -D"));
+        }
+        for option in args[i + 1].split(',') {
+            match option {
+                "rates" => config.debug_rates = true,
+                "stat" => config.debug_stat = true,
+                "xdev" => config.debug_xdev = true,
+                _ => {
+                    return Err(From::from(format!(
+                        "unknown debug option '{}' for -D",
+                        option
+                    )))
+                }
+            }
+        }
+        i += 2;
+    }
+    config.quoting_style = quoting_style
+        .unwrap_or_else(|| matchers::QuotingStyle::default_for(config.output_is_terminal));
     while i < args.len()
         && (args[i] == "-" || !args[i].starts_with('-'))
         && args[i] != "!"
@@ -92,7 +262,7 @@ fn parse_args(args: &[&str]) -> Result<ParsedInfo, Box<dyn Error>> {
         paths.push(args[i].to_string());
         i += 1;
     }
-    if i == 0 {
+    if paths.is_empty() {
         paths.push(".".to_string());
     }
     let matcher = matchers::build_top_level_matcher(&args[i..], &mut config)?;
@@ -103,19 +273,153 @@ fn parse_args(args: &[&str]) -> Result<ParsedInfo, Box<dyn Error>> {
     })
 }
 
+/// How many entries to process between progress reports in verbose mode.
+const PROGRESS_INTERVAL: u64 = 1000;
+
+/// Counts what's been scanned so far, for verbose mode's progress reports
+/// (which go to stderr, so as not to get mixed up with the results).
+#[derive(Default)]
+struct Progress {
+    directories: u64,
+    files: u64,
+}
+
+impl Progress {
+    fn record(&mut self, entry: &DirEntry) {
+        if entry.file_type().is_dir() {
+            self.directories += 1;
+        } else {
+            self.files += 1;
+        }
+        if (self.directories + self.files).is_multiple_of(PROGRESS_INTERVAL) {
+            let current_dir = if entry.file_type().is_dir() {
+                entry.path()
+            } else {
+                entry.path().parent().unwrap_or_else(|| Path::new(""))
+            };
+            writeln!(
+                &mut stderr(),
+                "find: scanned {} directories and {} files, now in {}",
+                self.directories,
+                self.files,
+                current_dir.to_string_lossy()
+            )
+            .unwrap();
+        }
+    }
+
+    fn report_total(&self) {
+        writeln!(
+            &mut stderr(),
+            "find: scanned {} directories and {} files in total",
+            self.directories,
+            self.files
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(unix)]
+fn device_of(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// For -D xdev, reports a directory that -xdev won't descend into. walkdir
+/// does the actual skipping, but doesn't tell us about it, so this repeats
+/// its check: a directory on a different device from the starting point.
+fn report_xdev_boundary(entry: &DirEntry, root_device: u64, config: &Config) {
+    if entry.depth() == 0 || entry.depth() >= config.max_depth || !entry.file_type().is_dir() {
+        return;
+    }
+    let device = match entry.metadata().ok().as_ref().and_then(device_of) {
+        Some(device) => device,
+        None => return,
+    };
+    if device != root_device {
+        writeln!(
+            &mut stderr(),
+            "debug_xdev: not descending into {} (device {} is not {})",
+            entry.path().to_string_lossy(),
+            device,
+            root_device
+        )
+        .unwrap();
+    }
+}
+
+/// The outcome of walking a single starting point (or, from do_find, all of
+/// them).
+struct WalkResult {
+    found_count: u64,
+    /// Set if a matcher asked for the whole search to stop (e.g. -limit).
+    quit: bool,
+    /// Set if any error was reported along the way, so that (as with GNU
+    /// find) we carry on but exit with a failure code at the end.
+    had_error: bool,
+}
+
 fn process_dir<'a>(
     dir: &str,
     config: &Config,
     deps: &'a dyn Dependencies<'a>,
     matcher: &dyn matchers::Matcher,
-) -> u64 {
+    progress: &mut Progress,
+) -> Result<WalkResult, Box<dyn Error>> {
     let mut found_count: u64 = 0;
+    let mut had_error = false;
+    // walkdir compares against the device of the starting point itself
+    // (following it if it's a symlink), so we do the same
+    let root_device = if config.same_file_system && config.debug_xdev {
+        fs::metadata(dir).ok().as_ref().and_then(device_of)
+    } else {
+        None
+    };
+    // Note that walkdir may still open a directory at max_depth, but it
+    // throws away the contents (and any error from reading them) without
+    // yielding anything, so nothing is ever reported for directories we
+    // wouldn't have descended into anyway.
     let mut walkdir = WalkDir::new(dir)
         .contents_first(config.depth_first)
         .max_depth(config.max_depth)
-        .min_depth(config.min_depth);
-    if config.sorted_output {
-        walkdir = walkdir.sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        .min_depth(config.min_depth)
+        // Mount points themselves are still yielded (and so tested), only
+        // their contents are skipped.
+        .same_file_system(config.same_file_system);
+    if config.dirs_first
+        || config.files_first
+        || config.sorted_output
+        || config.reverse_sorted_output
+    {
+        let (dirs_first, files_first) = (config.dirs_first, config.files_first);
+        let (sorted, reverse_sorted) = (config.sorted_output, config.reverse_sorted_output);
+        // Directories and files are grouped first, then sorted by name within
+        // each group. sort_by is stable, so without -sorted each group stays
+        // in the order the OS gave us.
+        walkdir = walkdir.sort_by(move |a, b| {
+            let (a_is_dir, b_is_dir) = (a.file_type().is_dir(), b.file_type().is_dir());
+            let by_type = if dirs_first {
+                b_is_dir.cmp(&a_is_dir)
+            } else if files_first {
+                a_is_dir.cmp(&b_is_dir)
+            } else {
+                Ordering::Equal
+            };
+            by_type.then_with(|| {
+                if reverse_sorted {
+                    b.file_name().cmp(a.file_name())
+                } else if sorted {
+                    a.file_name().cmp(b.file_name())
+                } else {
+                    Ordering::Equal
+                }
+            })
+        });
     }
 
     // Slightly yucky loop handling here :-(. See docs for
@@ -124,56 +428,201 @@ fn process_dir<'a>(
     loop {
         match it.next() {
             None => break,
-            Some(Err(err)) => writeln!(&mut stderr(), "Error: {}: {}", dir, err).unwrap(),
+            Some(Err(err)) => {
+                if config.stop_on_error {
+                    return Err(From::from(format!("{}: {}", dir, err)));
+                }
+                writeln!(&mut stderr(), "Error: {}: {}", dir, err).unwrap();
+                had_error = true;
+            }
             Some(Ok(entry)) => {
-                let mut matcher_io = matchers::MatcherIO::new(deps);
+                if config.verbose {
+                    progress.record(&entry);
+                }
+                if let Some(root_device) = root_device {
+                    report_xdev_boundary(&entry, root_device, config);
+                }
+                let mut matcher_io = matchers::MatcherIO::new(deps, config);
                 if matcher.matches(&entry, &mut matcher_io) {
                     found_count += 1;
                 }
-                if matcher_io.should_skip_current_dir() {
+                if matcher_io.had_error() {
+                    if config.stop_on_error {
+                        return Err(From::from(
+                            "stopping after the first error, as -stop-on-error was given",
+                        ));
+                    }
+                    had_error = true;
+                }
+                if matcher_io.should_quit() {
+                    return Ok(WalkResult {
+                        found_count,
+                        quit: true,
+                        had_error,
+                    });
+                }
+                // Only a -prune that was actually evaluated (and so
+                // returned true) asks for this, whatever the result of
+                // the expression as a whole. Pruning only means anything
+                // for directories we're about to descend into: walkdir
+                // would otherwise skip the rest of the parent directory.
+                // walkdir has already opened the directory by now, but
+                // nothing is read from it, and any error opening it is
+                // thrown away with it (so a pruned, unreadable start point
+                // is fine).
+                if matcher_io.should_skip_current_dir()
+                    && entry.file_type().is_dir()
+                    && !config.depth_first
+                {
                     it.skip_current_dir();
                 }
             }
         }
     }
-    found_count
+    Ok(WalkResult {
+        found_count,
+        quit: false,
+        had_error,
+    })
 }
 
-fn do_find<'a>(args: &[&str], deps: &'a dyn Dependencies<'a>) -> Result<u64, Box<dyn Error>> {
-    let paths_and_matcher = parse_args(args)?;
+fn do_find<'a>(
+    args: &[&str],
+    deps: &'a dyn Dependencies<'a>,
+) -> Result<WalkResult, Box<dyn Error>> {
+    let paths_and_matcher = parse_args(args, deps)?;
     if paths_and_matcher.config.help_requested {
         print_help();
-        return Ok(0);
+        return Ok(WalkResult {
+            found_count: 0,
+            quit: false,
+            had_error: false,
+        });
     }
     let mut found_count: u64 = 0;
+    let mut quit = false;
+    let mut had_error = false;
+    let mut progress = Progress::default();
     for path in paths_and_matcher.paths {
-        found_count += process_dir(
+        // a start point that doesn't exist (or can't be read) is reported
+        // like any other traversal error, so the rest are still searched
+        let result = process_dir(
             &path,
             &paths_and_matcher.config,
             deps,
             &*paths_and_matcher.matcher,
-        );
+            &mut progress,
+        )?;
+        found_count += result.found_count;
+        had_error |= result.had_error;
+        if result.quit {
+            quit = true;
+            break;
+        }
+    }
+    if paths_and_matcher.config.debug_rates {
+        writeln!(&mut stderr(), "Predicate success rates after completion:").unwrap();
     }
-    Ok(found_count)
+    let mut matcher_io = matchers::MatcherIO::new(deps, &paths_and_matcher.config);
+    paths_and_matcher.matcher.finished(&mut matcher_io);
+    had_error |= matcher_io.had_error();
+    if paths_and_matcher.config.verbose {
+        progress.report_total();
+    }
+    Ok(WalkResult {
+        found_count,
+        quit,
+        had_error,
+    })
 }
 
 fn print_help() {
     println!(
-        r"Usage: find [path...] [expression]
+        r"Usage: find [-v] [-D debugopts] [--quoting-style=STYLE] [--ignore-case]
+            [--dry-run] [--delete-stats] [--brace-expansion]
+            [--dirs-first|--files-first] [path...] [expression]
 
 If no path is supplied then the current working directory is used by default.
 
+-v or --verbose reports progress (the number of directories and files
+scanned, and the current directory) on stderr every 1000 entries.
+
+--quoting-style sets how -print quotes file names, as for ls: literal, shell,
+shell-always, c or escape, or escape-control, which only escapes control
+characters. The default is escape-control when printing to a terminal and
+literal otherwise.
+
+--ignore-case makes every -name and -regex test behave like -iname and
+-iregex.
+
+--brace-expansion lets -name and -iname patterns contain shell-style
+alternatives, so -name '{{*.c,*.h}}' matches names ending in either .c or .h.
+
+--dry-run makes -delete, -exec, -execdir and -execsh report what they would
+do on stderr instead of doing it. They then act as if they had succeeded.
+
+--delete-stats reports on stderr, once the search is over, how many files and
+directories -delete removed (or would have, with --dry-run) and how many
+bytes that freed.
+
+--dirs-first and --files-first list each directory's subdirectories before
+(or after) its other entries. Combined with -sorted or -sorted-reverse, each
+group is sorted by name.
+
+Debug options for -D are a comma-separated list of
+ rates
+    print how often each predicate was evaluated and how often it matched
+ stat
+    print each stat call made while testing files
+ xdev
+    print each directory that -xdev/-mount doesn't descend into
+
 Early alpha implementation. Currently the only expressions supported are
  -print
  -name case-sensitive_filename_pattern
  -iname case-insensitive_filename_pattern
- -type type_char
-    currently type_char can only be f (for file) or d (for directory)
+ -regex pattern
+ -iregex pattern
+    the pattern has to match the whole path
+ -regextype type
+    sets the flavour of the following -regex/-iregex tests: emacs (or
+    default), posix-basic (or sed) or posix-extended (or egrep)
+ -regex-anchor full|prefix|substring
+    a non-standard extension that sets whether the following -regex/-iregex
+    tests have to match the whole path (the default), just its start, or
+    any part of it
+ -type type_char[,type_char...]
+    type_char can be f (file), d (directory), l (symlink), b (block
+    device), c (character device), p (named pipe), s (socket) or, as a
+    non-standard extension, w (a union file system's whiteout)
  -size [+-]N[bcwkMG]
+ -size N1[bcwkMG]-N2[bcwkMG]
+    a non-standard extension matching sizes from N1 to N2 inclusive
+ -size-disk [+-]N[bcwkMG]
+    a non-standard extension like -size, but using the space the file
+    takes up on disk rather than its apparent size
+ -sizelike path_to_file
+    a non-standard extension that matches files of exactly the same size
+    as path_to_file. symlinks aren't followed, so if path_to_file is one
+    this is the size of the link itself
+ -empty
+ -readable
+ -writable
+ -executable
  -delete
  -prune
+ -limit N
+    a non-standard extension that matches the first N files it is applied to
+    and then stops the search, like -quit.
+ -count
+    a non-standard extension that prints just the number of files it was
+    applied to, once the search is over
+ -newest
+ -oldest
+    non-standard extensions that print just the most (or least) recently
+    modified file they were applied to, once the search is over
  -not
- -a
+ -a[nd]
  -o[r]
  ,
  ()
@@ -181,16 +630,45 @@ Early alpha implementation. Currently the only expressions supported are
  -false
  -maxdepth N
  -mindepth N
+ -xdev
+ -mount
  -d[epth]
  -ctime [+-]N
  -atime [+-]N
  -mtime [+-]N
+ -within N[smhdw]
+    a non-standard extension that matches files modified within the given
+    number of seconds (the default), minutes, hours, days or weeks
  -perm [-/]{{octal|u=rwx,go=w}}
  -newer path_to_file
+ -samefile path_to_file
+    symlinks aren't followed, so if path_to_file is one this matches the
+    link itself
+ -newerXY reference
+    X and Y are each a (access), B (birth), c (change) or m (modification)
+    time; Y can also be t, making reference a date like @EPOCH_SECONDS or
+    YYYY-MM-DD[ HH:MM[:SS]][Z], or now (the time find was started)
  -exec[dir] executable [args] [{{}}] [more args] ;
+    as a non-standard extension, {{{{}}}} passes a literal {{}}
+ -execsh 'shell command [{{}}]'
+    a non-standard extension that runs the command with sh -c, passing the
+    filename in place of {{}} without it being subject to shell expansion.
+    {{}} shouldn't be put in single quotes, which would stop that working.
+ -exec-timeout SECONDS
+    a non-standard extension that kills any -exec or -execsh command that
+    is still running after SECONDS, which then counts as an error.
  -sorted
     a non-standard extension that sorts directory contents by name before
     processing them. Less efficient, but allows for deterministic output.
+ -sorted-reverse
+    like -sorted, but sorts directory contents in descending order.
+ -color[=always|never|auto]
+    a non-standard extension that colorizes printed paths by file type,
+    using LS_COLORS like ls --color. Plain -color is the same as -color=auto,
+    which only uses colors when printing to a terminal.
+ -stop-on-error
+    a non-standard extension that aborts the search (with a non-zero exit
+    code) the first time an error is encountered.
 "
     );
 }
@@ -202,6 +680,7 @@ Early alpha implementation. Currently the only expressions supported are
 /// the name of the executable.
 pub fn find_main<'a>(args: &[&str], deps: &'a dyn Dependencies<'a>) -> i32 {
     match do_find(&args[1..], deps) {
+        Ok(result) if result.had_error => 1,
         Ok(_) => 0,
         Err(e) => {
             writeln!(&mut stderr(), "Error: {}", e).unwrap();
@@ -241,6 +720,8 @@ mod tests {
     pub struct FakeDependencies {
         pub output: RefCell<Cursor<Vec<u8>>>,
         now: SystemTime,
+        terminal: bool,
+        config: Config,
     }
 
     impl<'a> FakeDependencies {
@@ -248,6 +729,8 @@ mod tests {
             FakeDependencies {
                 output: RefCell::new(Cursor::new(Vec::<u8>::new())),
                 now: SystemTime::now(),
+                terminal: false,
+                config: Config::default(),
             }
         }
 
@@ -255,8 +738,13 @@ mod tests {
             self.now = new_time;
         }
 
-        pub fn new_matcher_io(&'a self) -> MatcherIO<'a> {
-            MatcherIO::new(self)
+        /// Pretends that the output is a terminal.
+        pub fn set_terminal(&mut self, terminal: bool) {
+            self.terminal = terminal;
+        }
+
+        pub fn new_matcher_io(&'a self) -> MatcherIO<'a, 'a> {
+            MatcherIO::new(self, &self.config)
         }
 
         pub fn get_output_as_string(&self) -> String {
@@ -276,19 +764,25 @@ mod tests {
         fn now(&'a self) -> SystemTime {
             self.now
         }
+
+        fn is_terminal(&'a self) -> bool {
+            self.terminal
+        }
     }
 
     #[test]
     fn parse_args_handles_single_dash() {
         // Apparently "-" should be treated as a directory name.
-        let parsed_info = super::parse_args(&["-"]).expect("parsing should succeed");
+        let deps = FakeDependencies::new();
+        let parsed_info = super::parse_args(&["-"], &deps).expect("parsing should succeed");
         assert_eq!(parsed_info.paths, ["-"]);
     }
 
     #[test]
     fn parse_args_bad_flag() {
         //
-        let result = super::parse_args(&["-asdadsafsfsadcs"]);
+        let deps = FakeDependencies::new();
+        let result = super::parse_args(&["-asdadsafsfsadcs"], &deps);
         if let Err(e) = result {
             assert_eq!(e.to_string(), "Unrecognized flag: '-asdadsafsfsadcs'");
         } else {
@@ -343,6 +837,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_sorted_reverse() {
+        let deps = FakeDependencies::new();
+
+        let rc = find_main(
+            &[
+                "find",
+                &fix_up_slashes("./test_data/depth"),
+                "-sorted-reverse",
+            ],
+            &deps,
+        );
+
+        assert_eq!(rc, 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            fix_up_slashes(
+                "./test_data/depth\n\
+                 ./test_data/depth/f0\n\
+                 ./test_data/depth/1\n\
+                 ./test_data/depth/1/f1\n\
+                 ./test_data/depth/1/2\n\
+                 ./test_data/depth/1/2/f2\n\
+                 ./test_data/depth/1/2/3\n\
+                 ./test_data/depth/1/2/3/f3\n"
+            )
+        );
+    }
+
+    #[test]
+    fn find_dirs_first_and_files_first() {
+        let deps = FakeDependencies::new();
+        let rc = find_main(
+            &[
+                "find",
+                "--dirs-first",
+                &fix_up_slashes("./test_data/depth"),
+                "-sorted-reverse",
+            ],
+            &deps,
+        );
+
+        assert_eq!(rc, 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            fix_up_slashes(
+                "./test_data/depth\n\
+                 ./test_data/depth/1\n\
+                 ./test_data/depth/1/2\n\
+                 ./test_data/depth/1/2/3\n\
+                 ./test_data/depth/1/2/3/f3\n\
+                 ./test_data/depth/1/2/f2\n\
+                 ./test_data/depth/1/f1\n\
+                 ./test_data/depth/f0\n"
+            )
+        );
+
+        let deps = FakeDependencies::new();
+        let rc = find_main(
+            &[
+                "find",
+                "--files-first",
+                &fix_up_slashes("./test_data/depth"),
+                "-sorted",
+            ],
+            &deps,
+        );
+
+        assert_eq!(rc, 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            fix_up_slashes(
+                "./test_data/depth\n\
+                 ./test_data/depth/f0\n\
+                 ./test_data/depth/1\n\
+                 ./test_data/depth/1/f1\n\
+                 ./test_data/depth/1/2\n\
+                 ./test_data/depth/1/2/f2\n\
+                 ./test_data/depth/1/2/3\n\
+                 ./test_data/depth/1/2/3/f3\n"
+            )
+        );
+    }
+
     #[test]
     fn find_maxdepth() {
         let deps = FakeDependencies::new();
@@ -429,6 +1007,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_limit() {
+        let deps = FakeDependencies::new();
+
+        let rc = find_main(
+            &[
+                "find",
+                &fix_up_slashes("./test_data/depth"),
+                &fix_up_slashes("./test_data/simple"),
+                "-sorted",
+                "-type",
+                "f",
+                "-limit",
+                "3",
+                "-print",
+            ],
+            &deps,
+        );
+
+        assert_eq!(rc, 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            fix_up_slashes(
+                "./test_data/depth/1/2/3/f3\n\
+                 ./test_data/depth/1/2/f2\n\
+                 ./test_data/depth/1/f1\n"
+            )
+        );
+
+        // the limit applies across starting points too
+        let deps = FakeDependencies::new();
+        let rc = find_main(
+            &[
+                "find",
+                &fix_up_slashes("./test_data/depth/f0"),
+                &fix_up_slashes("./test_data/simple"),
+                "-sorted",
+                "-limit",
+                "2",
+            ],
+            &deps,
+        );
+
+        assert_eq!(rc, 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            fix_up_slashes(
+                "./test_data/depth/f0\n\
+                 ./test_data/simple\n"
+            )
+        );
+    }
+
     #[test]
     fn find_zero_maxdepth() {
         let deps = FakeDependencies::new();
@@ -470,6 +1101,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_type_of_start_point() {
+        // There's no -printf %y yet, but -type classifies the start point the
+        // same way, with or without a trailing slash.
+        for root in &["./test_data/simple", "./test_data/simple/"] {
+            let deps = FakeDependencies::new();
+            let rc = find_main(
+                &[
+                    "find",
+                    &fix_up_slashes(root),
+                    "-maxdepth",
+                    "0",
+                    "-type",
+                    "d",
+                ],
+                &deps,
+            );
+
+            assert_eq!(rc, 0);
+            assert_eq!(
+                deps.get_output_as_string(),
+                fix_up_slashes(&format!("{}\n", root))
+            );
+        }
+    }
+
     #[test]
     fn find_mindepth() {
         let deps = FakeDependencies::new();
@@ -542,7 +1199,7 @@ mod tests {
         assert_eq!(rc, 0);
         assert_eq!(
             deps.get_output_as_string(),
-            (&new_dir).path().to_string_lossy().to_string() + "\n"
+            new_dir.path().to_string_lossy().to_string() + "\n"
         );
 
         // now do it the other way around, and nothing should be output
@@ -667,4 +1324,94 @@ mod tests {
         assert_eq!(rc, 0);
         assert_eq!(deps.get_output_as_string(), "");
     }
+
+    #[test]
+    fn find_size_of_directory() {
+        // a directory's size is its own st_size, not that of its contents
+        let temp_dir = TempDir::new("find_size_of_directory").unwrap();
+        fs::write(temp_dir.path().join("100k"), vec![0; 100 << 10]).unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        let dir_size = fs::metadata(temp_dir.path()).unwrap().len();
+
+        for size in &[format!("{}c", dir_size), format!("-{}c", 100 << 10)] {
+            let deps = FakeDependencies::new();
+            let rc = find_main(
+                &["find", &temp_dir_path, "-maxdepth", "0", "-size", size],
+                &deps,
+            );
+
+            assert_eq!(rc, 0);
+            assert_eq!(deps.get_output_as_string(), format!("{}\n", temp_dir_path));
+        }
+    }
+
+    #[test]
+    fn find_ignore_case() {
+        for expression in &[["-name", "abbbc"], ["-regex", ".*/abbbc"]] {
+            let deps = FakeDependencies::new();
+            let rc = find_main(
+                &[
+                    "find",
+                    "--ignore-case",
+                    &fix_up_slashes("./test_data/simple"),
+                    "-sorted",
+                    expression[0],
+                    expression[1],
+                ],
+                &deps,
+            );
+
+            assert_eq!(rc, 0);
+            assert_eq!(
+                deps.get_output_as_string(),
+                fix_up_slashes(
+                    "./test_data/simple/abbbc\n\
+                     ./test_data/simple/subdir/ABBBC\n"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn find_count() {
+        let deps = FakeDependencies::new();
+        let rc = find_main(
+            &[
+                "find",
+                &fix_up_slashes("./test_data/simple"),
+                "-type",
+                "f",
+                "-count",
+            ],
+            &deps,
+        );
+
+        assert_eq!(rc, 0);
+        assert_eq!(deps.get_output_as_string(), "2\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn find_default_quoting_style() {
+        let temp_dir = TempDir::new("find_default_quoting_style").unwrap();
+        fs::File::create(temp_dir.path().join("two\nlines")).unwrap();
+        let temp_dir_path = temp_dir.path().to_string_lossy();
+        let args = ["find", &temp_dir_path, "-name", "two*"];
+
+        // control characters are only escaped when printing to a terminal
+        let deps = FakeDependencies::new();
+        assert_eq!(find_main(&args, &deps), 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            format!("{}/two\nlines\n", temp_dir_path)
+        );
+
+        let mut deps = FakeDependencies::new();
+        deps.set_terminal(true);
+        assert_eq!(find_main(&args, &deps), 0);
+        assert_eq!(
+            deps.get_output_as_string(),
+            format!("{}/two\\nlines\n", temp_dir_path)
+        );
+    }
 }
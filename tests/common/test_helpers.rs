@@ -12,7 +12,7 @@ use std::vec::Vec;
 use walkdir::{DirEntry, WalkDir};
 
 use findutils::find::matchers::MatcherIO;
-use findutils::find::Dependencies;
+use findutils::find::{Config, Dependencies};
 
 /// A copy of find::tests::FakeDependencies.
 /// TODO: find out how to share #[cfg(test)] functions/structs between unit
@@ -20,6 +20,7 @@ use findutils::find::Dependencies;
 pub struct FakeDependencies {
     pub output: RefCell<Cursor<Vec<u8>>>,
     now: SystemTime,
+    config: Config,
 }
 
 impl<'a> FakeDependencies {
@@ -27,11 +28,12 @@ impl<'a> FakeDependencies {
         FakeDependencies {
             output: RefCell::new(Cursor::new(Vec::<u8>::new())),
             now: SystemTime::now(),
+            config: Config::default(),
         }
     }
 
-    pub fn new_matcher_io(&'a self) -> MatcherIO<'a> {
-        MatcherIO::new(self)
+    pub fn new_matcher_io(&'a self) -> MatcherIO<'a, 'a> {
+        MatcherIO::new(self, &self.config)
     }
 
     pub fn get_output_as_string(&self) -> String {
@@ -51,6 +53,10 @@ impl<'a> Dependencies<'a> for FakeDependencies {
     fn now(&'a self) -> SystemTime {
         self.now
     }
+
+    fn is_terminal(&'a self) -> bool {
+        false
+    }
 }
 
 pub fn path_to_testing_commandline() -> String {
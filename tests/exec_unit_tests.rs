@@ -32,7 +32,7 @@ fn matching_executes_code() {
     let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
     let matcher = SingleExecMatcher::new(
         &path_to_testing_commandline(),
-        &vec![temp_dir_path.as_ref(), "abc", "{}", "xyz"],
+        &[temp_dir_path.as_ref(), "abc", "{}", "xyz"],
         false,
     )
     .expect("Failed to create matcher");
@@ -60,7 +60,7 @@ fn matching_executes_code_in_files_directory() {
     let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
     let matcher = SingleExecMatcher::new(
         &path_to_testing_commandline(),
-        &vec![temp_dir_path.as_ref(), "abc", "{}", "xyz"],
+        &[temp_dir_path.as_ref(), "abc", "{}", "xyz"],
         true,
     )
     .expect("Failed to create matcher");
@@ -80,6 +80,63 @@ fn matching_executes_code_in_files_directory() {
     );
 }
 
+#[test]
+fn matching_replaces_embedded_braces() {
+    let temp_dir = TempDir::new("matching_replaces_embedded_braces").unwrap();
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+    let matcher = SingleExecMatcher::new(
+        &path_to_testing_commandline(),
+        &[temp_dir_path.as_ref(), "pre-{}-post", "{}{}"],
+        false,
+    )
+    .expect("Failed to create matcher");
+    let deps = FakeDependencies::new();
+    assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+
+    let mut f = File::open(temp_dir.path().join("1.txt")).expect("Failed to open output file");
+    let mut s = String::new();
+    f.read_to_string(&mut s)
+        .expect("failed to read output file");
+    assert_eq!(
+        s,
+        fix_up_slashes(&format!(
+            "cwd={}\nargs=\npre-test_data/simple/abbbc-post\n\
+             test_data/simple/abbbctest_data/simple/abbbc\n",
+            env::current_dir().unwrap().to_string_lossy()
+        ))
+    );
+}
+
+#[test]
+fn matching_passes_escaped_braces_literally() {
+    let temp_dir = TempDir::new("matching_passes_escaped_braces_literally").unwrap();
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+    let matcher = SingleExecMatcher::new(
+        &path_to_testing_commandline(),
+        &[temp_dir_path.as_ref(), "{{}}", "{}", "x{{}}{}"],
+        false,
+    )
+    .expect("Failed to create matcher");
+    let deps = FakeDependencies::new();
+    assert!(matcher.matches(&abbbc, &mut deps.new_matcher_io()));
+
+    let mut f = File::open(temp_dir.path().join("1.txt")).expect("Failed to open output file");
+    let mut s = String::new();
+    f.read_to_string(&mut s)
+        .expect("failed to read output file");
+    assert_eq!(
+        s,
+        fix_up_slashes(&format!(
+            "cwd={}\nargs=\n{{}}\ntest_data/simple/abbbc\nx{{}}test_data/simple/abbbc\n",
+            env::current_dir().unwrap().to_string_lossy()
+        ))
+    );
+}
+
 #[test]
 /// Running "find . -execdir whatever \;" failed with a No such file or directory error.
 /// It's now fixed, and this is a regression test to check that it stays fixed.
@@ -94,7 +151,7 @@ fn execdir_in_current_directory() {
         .expect("result wasn't OK");
     let matcher = SingleExecMatcher::new(
         &path_to_testing_commandline(),
-        &vec![temp_dir_path.as_ref(), "abc", "{}", "xyz"],
+        &[temp_dir_path.as_ref(), "abc", "{}", "xyz"],
         true,
     )
     .expect("Failed to create matcher");
@@ -122,7 +179,7 @@ fn matching_fails_if_executable_fails() {
     let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
     let matcher = SingleExecMatcher::new(
         &path_to_testing_commandline(),
-        &vec![
+        &[
             temp_dir_path.as_ref(),
             "--exit_with_failure",
             "abc",
@@ -148,3 +205,25 @@ fn matching_fails_if_executable_fails() {
         ))
     );
 }
+
+#[test]
+fn matching_fails_if_command_line_too_long() {
+    let temp_dir = TempDir::new("matching_fails_if_command_line_too_long").unwrap();
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    let abbbc = get_dir_entry_for("test_data/simple", "abbbc");
+    let matcher = SingleExecMatcher::new(
+        &path_to_testing_commandline(),
+        &[temp_dir_path.as_ref(), "abc", "{}", "xyz"],
+        false,
+    )
+    .expect("Failed to create matcher");
+    let deps = FakeDependencies::new();
+    let mut matcher_io = deps.new_matcher_io();
+    matcher_io.set_arg_max(100);
+    assert!(!matcher.matches(&abbbc, &mut matcher_io));
+    assert!(matcher_io.had_error());
+
+    // the command should never have been run
+    assert!(!temp_dir.path().join("1.txt").exists());
+}
@@ -12,9 +12,13 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use serial_test::serial;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use tempdir::TempDir;
 
+use common::test_helpers::*;
+
+mod common;
+
 #[serial(working_dir)]
 #[test]
 fn no_args() {
@@ -31,7 +35,7 @@ fn no_args() {
 fn two_matchers_both_match() {
     Command::cargo_bin("find")
         .expect("found binary")
-        .args(&["-type", "d", "-name", "test_data"])
+        .args(["-type", "d", "-name", "test_data"])
         .assert()
         .success()
         .stderr(predicate::str::is_empty())
@@ -43,7 +47,7 @@ fn two_matchers_both_match() {
 fn two_matchers_one_matches() {
     Command::cargo_bin("find")
         .expect("found binary")
-        .args(&["-type", "f", "-name", "test_data"])
+        .args(["-type", "f", "-name", "test_data"])
         .assert()
         .success()
         .stderr(predicate::str::is_empty())
@@ -59,7 +63,7 @@ fn matcher_with_side_effects_at_end() {
 
     Command::cargo_bin("find")
         .expect("found binary")
-        .args(&[&temp_dir_path, "-name", "test", "-delete"])
+        .args([&temp_dir_path, "-name", "test", "-delete"])
         .assert()
         .success()
         .stderr(predicate::str::is_empty())
@@ -78,7 +82,7 @@ fn matcher_with_side_effects_in_front() {
 
     Command::cargo_bin("find")
         .expect("found binary")
-        .args(&[&temp_dir_path, "-delete", "-name", "test"])
+        .args([&temp_dir_path, "-delete", "-name", "test"])
         .assert()
         .success()
         .stderr(predicate::str::is_empty())
@@ -98,12 +102,12 @@ fn matcher_with_side_effects_in_front() {
 fn delete_on_dot_dir() {
     let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
     let original_dir = env::current_dir().unwrap();
-    env::set_current_dir(&temp_dir.path()).expect("working dir changed");
+    env::set_current_dir(temp_dir.path()).expect("working dir changed");
 
     // "." should be matched (confirmed by the print), but not deleted.
     Command::cargo_bin("find")
         .expect("found binary")
-        .args(&[".", "-delete", "-print"])
+        .args([".", "-delete", "-print"])
         .assert()
         .success()
         .stderr(predicate::str::is_empty())
@@ -113,3 +117,1076 @@ fn delete_on_dot_dir() {
 
     assert!(temp_dir.path().exists(), "temp dir should still exist");
 }
+
+// There's no -printf yet, but -print renders paths the same way %p will, so
+// pin down the GNU behaviour for a "." start point here.
+#[serial(working_dir)]
+#[test]
+fn print_dot_start_point() {
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    File::create(temp_dir.path().join("child")).expect("created test file");
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).expect("working dir changed");
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([".", "-sorted"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(fix_up_slashes(".\n./child\n")));
+
+    env::set_current_dir(original_dir).expect("restored original working dir");
+}
+
+#[test]
+fn name_of_start_point_with_trailing_slashes() {
+    // there's no -printf %f yet, but -name works off the same basename, which
+    // must ignore any trailing slashes, while the printed path keeps them
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["test_data/simple///", "-maxdepth", "0", "-name", "simple"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar("test_data/simple///\n"));
+}
+
+// on windows the deeper paths would get a mix of separators
+#[cfg(unix)]
+#[test]
+fn print_start_point_with_trailing_slash() {
+    // as with GNU find, the start point is printed exactly as given, and its
+    // children don't get a doubled separator
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["test_data/simple/", "-sorted"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(
+            "test_data/simple/\n\
+             test_data/simple/abbbc\n\
+             test_data/simple/subdir\n\
+             test_data/simple/subdir/ABBBC\n",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn root_start_point() {
+    // there's no -printf %p/%f yet, but both the printed path and the name
+    // that -name sees should be "/" for the root itself
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["/", "-maxdepth", "0", "-name", "/"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar("/\n"));
+}
+
+#[serial(working_dir)]
+#[test]
+fn stop_on_error() {
+    // without the flag, a failed -exec is reported and the search carries on,
+    // but the exit code still reflects the failure
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-exec",
+            "this_command_does_not_exist",
+            ";",
+            ",",
+            "-print",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::similar(
+            "find: 'this_command_does_not_exist': No such file or directory\n".repeat(4),
+        ))
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple\n\
+             ./test_data/simple/abbbc\n\
+             ./test_data/simple/subdir\n\
+             ./test_data/simple/subdir/ABBBC\n",
+        )));
+
+    // with it, we give up straight after that first failure
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-stop-on-error",
+            "-exec",
+            "this_command_does_not_exist",
+            ";",
+            ",",
+            "-print",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("-stop-on-error"))
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple\n",
+        )));
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_of_failing_command_is_not_an_error() {
+    // unlike a command that can't be run at all, one that runs and fails
+    // just makes -exec false
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([&fix_up_slashes("./test_data/simple"), "-exec", "false", ";"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn stop_on_error_unreadable_dir() {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    let unreadable = temp_dir.path().join("a");
+    fs::create_dir(&unreadable).expect("created unreadable dir");
+    fs::create_dir(temp_dir.path().join("b")).expect("created readable dir");
+    fs::set_permissions(&unreadable, Permissions::from_mode(0o000)).expect("chmod");
+
+    // permissions don't stop root from reading anything, so there's nothing
+    // to test in that case.
+    if fs::read_dir(&unreadable).is_err() {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args([&temp_dir_path, "-sorted"])
+            .assert()
+            .stderr(predicate::str::contains("Permission denied"))
+            .stdout(predicate::str::contains("/b\n"));
+
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args([&temp_dir_path, "-sorted", "-stop-on-error"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Permission denied"))
+            .stdout(predicate::str::contains("/b\n").not());
+    }
+
+    fs::set_permissions(&unreadable, Permissions::from_mode(0o755)).expect("chmod");
+}
+
+#[cfg(unix)]
+#[test]
+fn maxdepth_unreadable_dir_at_boundary() {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    let unreadable = temp_dir.path().join("unreadable");
+    fs::create_dir(&unreadable).expect("created unreadable dir");
+    File::create(unreadable.join("hidden")).expect("created test file");
+    fs::set_permissions(&unreadable, Permissions::from_mode(0o000)).expect("chmod");
+
+    // The contents of the directory are beyond -maxdepth, so we never need
+    // to look inside it and shouldn't complain that we can't.
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([&temp_dir_path, "-maxdepth", "1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::contains("/unreadable\n"))
+        .stdout(predicate::str::contains("hidden").not());
+
+    fs::set_permissions(&unreadable, Permissions::from_mode(0o755)).expect("chmod");
+}
+
+#[cfg(unix)]
+#[test]
+fn execsh() {
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    File::create(temp_dir.path().join("plain")).expect("created test file");
+    // the filename shouldn't be expanded or executed by the shell
+    File::create(temp_dir.path().join("$(echo oops);*")).expect("created test file");
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &temp_dir_path,
+            "-type",
+            "f",
+            "-sorted",
+            "-execsh",
+            "echo {}",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(format!(
+            "{0}/$(echo oops);*\n{0}/plain\n",
+            temp_dir_path
+        )));
+}
+
+#[cfg(unix)]
+#[test]
+fn execsh_single_quoted_braces() {
+    // {} is replaced with "$1" even in single quotes, where the shell won't
+    // expand it
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-maxdepth",
+            "0",
+            "-execsh",
+            "echo '{}'",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar("\"$1\"\n"));
+}
+
+#[test]
+fn color_always() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .env("LS_COLORS", "di=01;34:fi=")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-color=always",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "\x1b[01;34m./test_data/simple\x1b[0m\n\
+             ./test_data/simple/abbbc\n\
+             \x1b[01;34m./test_data/simple/subdir\x1b[0m\n\
+             ./test_data/simple/subdir/ABBBC\n",
+        )));
+
+    // output isn't a terminal, so "auto" shouldn't add any colors
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .env("LS_COLORS", "di=01;34:fi=")
+        .args([&fix_up_slashes("./test_data/simple"), "-sorted", "-color"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn type_f_empty_skips_directories() {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    let unreadable = temp_dir.path().join("unreadable");
+    fs::create_dir(&unreadable).expect("created unreadable dir");
+    File::create(temp_dir.path().join("empty")).expect("created test file");
+    fs::set_permissions(&unreadable, Permissions::from_mode(0o000)).expect("chmod");
+
+    // -type f rules out the directory before -empty would have to read it,
+    // so there's no error even though it can't be read.
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([&temp_dir_path, "-maxdepth", "1", "-type", "f", "-empty"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(format!(
+            "{}/empty\n",
+            temp_dir_path
+        )));
+
+    // permissions don't stop root from reading anything
+    if fs::read_dir(&unreadable).is_err() {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args([&temp_dir_path, "-maxdepth", "1", "-empty"])
+            .assert()
+            .stderr(predicate::str::contains("Permission denied"));
+    }
+
+    fs::set_permissions(&unreadable, Permissions::from_mode(0o755)).expect("chmod");
+}
+
+#[cfg(unix)]
+#[test]
+fn xdev_includes_mount_point_but_not_contents() {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+
+    // only meaningful if /proc is a separate file system, as it usually is
+    // on Linux
+    let (root, proc_dir) = match (fs::metadata("/"), fs::metadata("/proc/self")) {
+        (Ok(root), Ok(proc_dir)) => (root, proc_dir),
+        _ => return,
+    };
+    if root.dev() == proc_dir.dev() {
+        return;
+    }
+
+    for arg in &["-xdev", "-mount"] {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args(["/", "-maxdepth", "1", arg, "-name", "proc"])
+            .assert()
+            .success()
+            .stdout(predicate::str::similar("/proc\n"));
+
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args(["/", "-maxdepth", "2", arg, "-name", "self"])
+            .assert()
+            .stdout(predicate::str::contains("/proc/self").not());
+    }
+
+    // and without the option we do descend into it
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["/proc", "-maxdepth", "1", "-name", "self"])
+        .assert()
+        .stdout(predicate::str::similar("/proc/self\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn debug_xdev_reports_mount_points() {
+    use std::os::unix::fs::MetadataExt;
+
+    // as above, this needs /proc to be a separate file system
+    let (root, proc_dir) = match (fs::metadata("/"), fs::metadata("/proc/self")) {
+        (Ok(root), Ok(proc_dir)) => (root, proc_dir),
+        _ => return,
+    };
+    if root.dev() == proc_dir.dev() {
+        return;
+    }
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "-D",
+            "xdev",
+            "/",
+            "-maxdepth",
+            "2",
+            "-xdev",
+            "-name",
+            "proc",
+        ])
+        .assert()
+        .stdout(predicate::str::similar("/proc\n"))
+        .stderr(predicate::str::contains(format!(
+            "debug_xdev: not descending into /proc (device {} is not {})\n",
+            proc_dir.dev(),
+            root.dev()
+        )));
+
+    // nothing to report without -xdev, as we do descend
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["-D", "xdev", "/", "-maxdepth", "1", "-name", "proc"])
+        .assert()
+        .stderr(predicate::str::contains("debug_xdev").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_stdin_is_dev_null() {
+    for args in &[&["-exec", "cat", ";"][..], &["-execsh", "cat"][..]] {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args([&fix_up_slashes("./test_data/simple/subdir"), "-type", "f"])
+            .args(*args)
+            .write_stdin("find's own input\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::is_empty());
+    }
+}
+
+#[test]
+fn debug_rates() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "-D",
+            "rates",
+            &fix_up_slashes("./test_data/simple"),
+            "-name",
+            "abbbc",
+            "-o",
+            "-type",
+            "d",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Predicate success rates after completion:\n",
+        ))
+        .stderr(predicate::str::contains("-name abbbc: 1/4=0.25\n"))
+        .stderr(predicate::str::contains("-type d: 2/3=0.67\n"))
+        .stdout(predicate::str::contains("abbbc"));
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["-D", "nonsense", "./test_data/simple"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown debug option 'nonsense'"));
+}
+
+#[test]
+fn bad_regex_names_pattern() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-regextype",
+            "posix-extended",
+            "-iregex",
+            "(ab",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "-iregex: invalid regular expression '(ab'",
+        ))
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn type_suggests_lowercase_letter() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([&fix_up_slashes("./test_data/simple"), "-type", "F"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("did you mean -type f?"))
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn debug_stat() {
+    // -type is answered from the directory entry, so only the files that
+    // get as far as -size should be stat'd
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "-D",
+            "stat",
+            &fix_up_slashes("./test_data/simple"),
+            "-type",
+            "f",
+            "-size",
+            "-1k",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(fix_up_slashes(
+            "debug_stat (lstat) ./test_data/simple/abbbc\n",
+        )))
+        .stderr(predicate::str::contains(fix_up_slashes(
+            "debug_stat (lstat) ./test_data/simple/subdir/ABBBC\n",
+        )))
+        .stderr(predicate::str::contains("subdir\n").not())
+        .stdout(predicate::str::contains("abbbc"));
+}
+
+#[test]
+fn prints_unicode_file_names() {
+    // output is always UTF-8. On a windows console std's stdout goes through
+    // WriteConsoleW, so we don't need to do anything special there either.
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    File::create(temp_dir.path().join("h\u{e9}llo_\u{4e16}\u{754c}")).expect("created test file");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([temp_dir_path.as_ref(), "-name", "h*"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(format!(
+            "{}\n",
+            temp_dir
+                .path()
+                .join("h\u{e9}llo_\u{4e16}\u{754c}")
+                .to_string_lossy()
+        )));
+}
+
+// windows doesn't allow newlines in file names
+#[cfg(unix)]
+#[test]
+fn quoting_style_escape() {
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    File::create(temp_dir.path().join("two\nlines")).expect("created test file");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "--quoting-style=escape",
+            temp_dir_path.as_ref(),
+            "-name",
+            "two*",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(format!(
+            "{}/two\\nlines\n",
+            temp_dir_path
+        )));
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["--quoting-style=fancy", temp_dir_path.as_ref()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid quoting style 'fancy'"));
+}
+
+// control characters are only escaped by default when printing to a terminal
+#[cfg(unix)]
+#[test]
+fn control_characters_printed_raw_to_pipe() {
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    File::create(temp_dir.path().join("bell\x07")).expect("created test file");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([temp_dir_path.as_ref(), "-name", "bell*"])
+        .assert()
+        .success()
+        .stdout(predicate::str::similar(format!(
+            "{}/bell\x07\n",
+            temp_dir_path
+        )));
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "--quoting-style=escape-control",
+            temp_dir_path.as_ref(),
+            "-name",
+            "bell*",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::similar(format!(
+            "{}/bell\\a\n",
+            temp_dir_path
+        )));
+}
+
+#[test]
+fn verbose_reports_progress_on_stderr() {
+    let temp_dir = TempDir::new("find_cmd_").expect("made temp dir");
+    for dir in &["a", "b", "c"] {
+        let dir = temp_dir.path().join(dir);
+        fs::create_dir(&dir).expect("created test dir");
+        for i in 0..400 {
+            File::create(dir.join(i.to_string())).expect("created test file");
+        }
+    }
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    let temp_dir_path: &str = &temp_dir_path;
+
+    let quiet = Command::cargo_bin("find")
+        .expect("found binary")
+        .args([temp_dir_path, "-sorted", "-name", "39*"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+    let expected_stdout = String::from_utf8(quiet.get_output().stdout.clone()).unwrap();
+
+    for arg in &["-v", "--verbose"] {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args([arg, temp_dir_path, "-sorted", "-name", "39*"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(format!(
+                "find: scanned 4 directories and 996 files, now in {}\n",
+                temp_dir.path().join("c").to_string_lossy()
+            )))
+            .stderr(predicate::str::contains(
+                "find: scanned 4 directories and 1200 files in total\n",
+            ))
+            .stdout(predicate::str::similar(expected_stdout.clone()));
+    }
+}
+
+#[test]
+fn negated_prune() {
+    // -prune's side effect happens whenever it's evaluated, regardless of
+    // the final result: here subdir isn't printed, but still isn't descended
+    // into
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "!",
+            "(",
+            "-name",
+            "subdir",
+            "-prune",
+            ")",
+            "-print",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple\n./test_data/simple/abbbc\n",
+        )));
+
+    // and here it's printed (! -prune being false), but still pruned
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-name",
+            "subdir",
+            "!",
+            "-prune",
+            "-o",
+            "-print",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple\n./test_data/simple/abbbc\n./test_data/simple/subdir\n",
+        )));
+}
+
+#[test]
+fn prune_start_point() {
+    // the start point is still acted on, just not descended into
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .current_dir("test_data/simple")
+        .args([".", "-prune", "-print"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(".\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn prune_unreadable_start_point() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // nothing is read from a pruned directory, so there's no error for one
+    // we can't read (as root, we can read it anyway)
+    let temp_dir = TempDir::new("prune_unreadable_start_point").expect("made temp dir");
+    let locked = temp_dir.path().join("locked");
+    fs::create_dir(&locked).expect("created test dir");
+    fs::write(locked.join("secret"), "").expect("created test file");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).expect("locked test dir");
+
+    let locked_path = locked.to_string_lossy();
+    let assert = Command::cargo_bin("find")
+        .expect("found binary")
+        .args([&locked_path, "-prune", "-print"])
+        .assert();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).expect("unlocked test dir");
+    assert
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(format!("{}\n", locked_path)));
+}
+
+#[test]
+fn prune_with_depth_has_no_effect() {
+    // with -depth a directory's contents have already been visited by the
+    // time -prune sees it, so (as with GNU find) it can't skip anything
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            // so that subdir isn't the last entry in its directory
+            "-sorted-reverse",
+            "-depth",
+            "-name",
+            "subdir",
+            "-prune",
+            "-o",
+            "-print",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple/subdir/ABBBC\n./test_data/simple/abbbc\n./test_data/simple\n",
+        )));
+}
+
+#[test]
+fn prune_of_file_has_no_effect() {
+    // pruning a file used to skip the rest of its directory
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-name",
+            "abbbc",
+            "-prune",
+            "-o",
+            "-print",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple\n./test_data/simple/subdir\n./test_data/simple/subdir/ABBBC\n",
+        )));
+}
+
+#[test]
+fn closed_stdout_stops_quietly() {
+    use assert_cmd::cargo::CommandCargoExt;
+    use std::process;
+
+    // as with `find | head`, but with the reading end closed before find
+    // even starts, so that the very first write is certain to fail
+    let (reader, writer) = std::io::pipe().expect("made pipe");
+    drop(reader);
+    let output = process::Command::cargo_bin("find")
+        .expect("found binary")
+        .args([&fix_up_slashes("./test_data/simple")])
+        .stdout(writer)
+        .output()
+        .expect("ran find");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_timeout_kills_command() {
+    let start = std::time::Instant::now();
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "./test_data/simple",
+            "-maxdepth",
+            "0",
+            "-exec-timeout",
+            "1",
+            "-exec",
+            "sleep",
+            "30",
+            ";",
+            "-print",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "find: 'sleep' timed out after 1s and was killed",
+        ))
+        .stdout(predicate::str::is_empty());
+    assert!(start.elapsed().as_secs() < 30, "sleep wasn't killed");
+
+    // commands that finish in time are unaffected
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "./test_data/simple",
+            "-maxdepth",
+            "0",
+            "-exec-timeout",
+            "30",
+            "-exec",
+            "true",
+            ";",
+            "-print",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar("./test_data/simple\n"));
+}
+
+#[test]
+fn dry_run_deletes_nothing() {
+    let temp_dir = TempDir::new("dry_run_deletes_nothing").expect("made temp dir");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    File::create(temp_dir.path().join("victim")).expect("created test file");
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "--dry-run",
+            &temp_dir_path,
+            "-name",
+            "victim",
+            "-delete",
+            "-exec",
+            "rm",
+            "{}",
+            ";",
+            "-print",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::similar(format!(
+            "would delete {0}/victim\nwould run rm {0}/victim\n",
+            temp_dir_path
+        )))
+        .stdout(predicate::str::similar(format!(
+            "{}/victim\n",
+            temp_dir_path
+        )));
+    assert!(temp_dir.path().join("victim").exists());
+
+    // arguments are quoted if need be, and -execdir shows where it would run
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "--dry-run",
+            &temp_dir_path,
+            "-name",
+            "victim",
+            "-execdir",
+            "rm",
+            "--",
+            "{} copy",
+            ";",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::similar(format!(
+            "would run rm -- './victim copy' in {}\n",
+            temp_dir_path
+        )))
+        .stdout(predicate::str::is_empty());
+    assert!(temp_dir.path().join("victim").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn newermt_local_time_honours_tz() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let temp_dir = TempDir::new("newermt_local_time_honours_tz").expect("made temp dir");
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    // 2000-01-01 07:00:00 UTC
+    File::create(temp_dir.path().join("file"))
+        .and_then(|f| f.set_modified(UNIX_EPOCH + Duration::from_secs(946_710_000)))
+        .expect("created test file");
+    let file_path = format!("{}/file", temp_dir_path);
+
+    // 03:00 is 03:00 UTC in UTC, but 08:00 UTC five hours west of it
+    for (tz, expected) in [
+        ("UTC0", format!("{}\n", file_path)),
+        ("EST5", String::new()),
+    ] {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .env("TZ", tz)
+            .args([&file_path, "-newermt", "2000-01-01 03:00"])
+            .assert()
+            .success()
+            .stderr(predicate::str::is_empty())
+            .stdout(predicate::str::similar(expected));
+    }
+}
+
+#[test]
+fn missing_start_point_does_not_stop_others() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "./test_data/does_not_exist",
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-print",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("./test_data/does_not_exist"))
+        .stderr(predicate::str::contains("test_data/simple").not())
+        .stdout(predicate::str::similar(fix_up_slashes(
+            "./test_data/simple\n\
+             ./test_data/simple/abbbc\n\
+             ./test_data/simple/subdir\n\
+             ./test_data/simple/subdir/ABBBC\n",
+        )));
+}
+
+#[test]
+fn delete_stats_summary() {
+    let temp_dir = TempDir::new("delete_stats_summary").expect("made temp dir");
+    let victims = temp_dir.path().join("victims");
+    fs::create_dir(&victims).expect("created test dir");
+    fs::write(victims.join("a"), "abc").expect("created test file");
+    fs::write(victims.join("b"), "defg").expect("created test file");
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["--delete-stats", &victims.to_string_lossy(), "-delete"])
+        .assert()
+        .success()
+        .stderr(predicate::str::similar(
+            "deleted 2 files and 1 directory (7 bytes)\n",
+        ))
+        .stdout(predicate::str::is_empty());
+    assert!(!victims.exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn delete_refuses_mount_point() {
+    use std::os::unix::fs::MetadataExt;
+
+    // only meaningful if /proc is a separate file system, as it usually is.
+    // rmdir can't remove a mount point anyway, so even as root nothing is at
+    // risk if the check doesn't work.
+    match (fs::metadata("/"), fs::metadata("/proc")) {
+        (Ok(root), Ok(proc_dir)) if root.dev() != proc_dir.dev() => {}
+        _ => return,
+    }
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["/proc", "-maxdepth", "0", "-delete"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::similar(
+            "Failed to delete /proc: refusing to delete a mount point\n",
+        ))
+        .stdout(predicate::str::is_empty());
+    assert!(fs::metadata("/proc").is_ok());
+}
+
+#[test]
+fn count_prints_only_the_total() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            &fix_up_slashes("./test_data/simple"),
+            "-type",
+            "f",
+            "-count",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty())
+        .stdout(predicate::str::similar("2\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_killed_by_signal_is_reported() {
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args([
+            "./test_data/simple",
+            "-sorted",
+            "-exec",
+            "sh",
+            "-c",
+            "kill -TERM $$",
+            ";",
+            "-o",
+            "-print",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::similar(
+            "find: 'sh' terminated by signal 15\n".repeat(4),
+        ))
+        .stdout(predicate::str::similar(
+            "./test_data/simple\n\
+             ./test_data/simple/abbbc\n\
+             ./test_data/simple/subdir\n\
+             ./test_data/simple/subdir/ABBBC\n",
+        ));
+}
+
+#[test]
+fn now_option_fixes_the_current_time() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let temp_dir = TempDir::new("now_option_fixes_the_current_time").expect("made temp dir");
+    let file_path = temp_dir.path().join("file");
+    File::create(&file_path)
+        .and_then(|f| f.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000_000)))
+        .expect("created test file");
+    let file_path = file_path.to_string_lossy();
+
+    // a day and a half later, the file is 1 day old (in whole days)
+    let now = (1_000_000_000 + 36 * 60 * 60).to_string();
+    for (days, expected) in [("0", String::new()), ("1", format!("{}\n", file_path))] {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args(["--now", &now, &file_path, "-mtime", days])
+            .assert()
+            .success()
+            .stderr(predicate::str::is_empty())
+            .stdout(predicate::str::similar(expected));
+    }
+
+    Command::cargo_bin("find")
+        .expect("found binary")
+        .args(["--now", "yesterday", &file_path])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Expected a number of seconds since the epoch for --now, but got `yesterday'",
+        ));
+}
+
+#[test]
+fn newest_prints_only_the_newest_file() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let temp_dir = TempDir::new("newest_prints_only_the_newest_file").expect("made temp dir");
+    for (name, seconds) in [("a", 3000), ("b", 1000), ("c", 2000)] {
+        File::create(temp_dir.path().join(name))
+            .and_then(|f| f.set_modified(UNIX_EPOCH + Duration::from_secs(seconds)))
+            .expect("created test file");
+    }
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+
+    for (action, expected) in [("-newest", "a"), ("-oldest", "b")] {
+        Command::cargo_bin("find")
+            .expect("found binary")
+            .args([&temp_dir_path, "-type", "f", action])
+            .assert()
+            .success()
+            .stderr(predicate::str::is_empty())
+            .stdout(predicate::str::similar(format!(
+                "{}/{}\n",
+                temp_dir_path, expected
+            )));
+    }
+}
@@ -106,3 +106,102 @@ fn find_execdir() {
         ))
     );
 }
+
+#[test]
+fn find_newer_with_execdir() {
+    // the reference is relative to the directory find was started in, and
+    // must not be re-resolved against the directory each -execdir command
+    // runs in.
+    let temp_dir = TempDir::new("find_newer_with_execdir").unwrap();
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    let new_dir = TempDir::new("find_newer_with_execdir_tree").unwrap();
+    let new_dir_path = new_dir.path().to_string_lossy();
+    File::create(new_dir.path().join("newFile")).expect("create temp file");
+    let deps = FakeDependencies::new();
+
+    let rc = find_main(
+        &[
+            "find",
+            &new_dir_path,
+            "-type",
+            "f",
+            "-newer",
+            &fix_up_slashes("./test_data/simple/abbbc"),
+            "-execdir",
+            &path_to_testing_commandline(),
+            temp_dir_path.as_ref(),
+            "{}",
+            ";",
+            "-newer",
+            &fix_up_slashes("./test_data/simple/abbbc"),
+            "-print",
+        ],
+        &deps,
+    );
+
+    assert_eq!(rc, 0);
+    assert_eq!(
+        deps.get_output_as_string(),
+        fix_up_slashes(&format!("{}/newFile\n", new_dir_path))
+    );
+
+    let mut f = File::open(temp_dir.path().join("1.txt")).expect("Failed to open output file");
+    let mut s = String::new();
+    f.read_to_string(&mut s)
+        .expect("failed to read output file");
+    assert_eq!(
+        s,
+        fix_up_slashes(&format!("cwd={}\nargs=\n./newFile\n", new_dir_path))
+    );
+}
+
+#[test]
+fn find_execdir_after_failed_spawn() {
+    // a command that can't be started mustn't leave find (or the commands
+    // run for later files) in the wrong directory
+    let temp_dir = TempDir::new("find_execdir_after_failed_spawn").unwrap();
+    let temp_dir_path = temp_dir.path().to_string_lossy();
+    let cwd = env::current_dir().unwrap();
+    let deps = FakeDependencies::new();
+
+    let rc = find_main(
+        &[
+            "find",
+            &fix_up_slashes("./test_data/simple"),
+            "-sorted",
+            "-type",
+            "f",
+            "(",
+            "-execdir",
+            "this-command-does-not-exist",
+            "{}",
+            ";",
+            ",",
+            "-execdir",
+            &path_to_testing_commandline(),
+            temp_dir_path.as_ref(),
+            "{}",
+            ";",
+            ")",
+        ],
+        &deps,
+    );
+
+    assert_eq!(rc, 1);
+    assert_eq!(env::current_dir().unwrap(), cwd);
+
+    for (file_name, expected) in &[
+        ("1.txt", "test_data/simple\nargs=\n./abbbc\n"),
+        ("2.txt", "test_data/simple/subdir\nargs=\n./ABBBC\n"),
+    ] {
+        let mut f =
+            File::open(temp_dir.path().join(file_name)).expect("Failed to open output file");
+        let mut s = String::new();
+        f.read_to_string(&mut s)
+            .expect("failed to read output file");
+        assert_eq!(
+            s,
+            fix_up_slashes(&format!("cwd={}/{}", cwd.to_string_lossy(), expected))
+        );
+    }
+}